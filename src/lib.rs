@@ -0,0 +1,225 @@
+#![allow(dead_code)]
+
+use std::borrow::Cow;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use clap::ArgEnum;
+use serde::Serialize;
+use strum::{EnumIter, IntoEnumIterator};
+
+pub mod ops;
+pub mod rnotev0_4;
+pub mod rnotev0_5;
+pub mod slot;
+
+extern crate nalgebra as na;
+extern crate parry2d_f64 as p2d;
+
+#[derive(ArgEnum, Copy, Clone, Debug, EnumIter, PartialEq, Eq)]
+pub enum Version {
+    V0_4,
+    V0_5,
+}
+
+#[derive(ArgEnum, Copy, Clone, Debug, EnumIter)]
+pub enum OutputType {
+    Json,
+    PrettyJson,
+    V0_4,
+    V0_5,
+    Latest,
+}
+
+impl Version {
+    /// Detects the version of a decompressed rnote file from its `version` field.
+    pub fn detect(json: &serde_json::Value) -> Result<Version> {
+        match json.get("version").and_then(|v| v.as_str()) {
+            Some(version) if version.starts_with("0.4") => Ok(Version::V0_4),
+            Some(version) if version.starts_with("0.5") => Ok(Version::V0_5),
+            Some(version) => Err(anyhow!("unsupported file version '{version}'")),
+            // v0.5 files always have the wrapper with the `data` field
+            None if json.get("data").is_some() => Ok(Version::V0_5),
+            None => Ok(Version::V0_4),
+        }
+    }
+}
+
+impl OutputType {
+    pub fn as_version(self) -> Option<Version> {
+        match self {
+            Self::V0_4 => Some(Version::V0_4),
+            Self::V0_5 | Self::Latest => Some(Version::V0_5),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum RnoteDocument {
+    V0_4(rnotev0_4::Sheet),
+    V0_5(rnotev0_5::RnotefileWrapper),
+}
+
+impl RnoteDocument {
+    pub fn version(&self) -> Version {
+        match self {
+            Self::V0_4(_) => Version::V0_4,
+            Self::V0_5(_) => Version::V0_5,
+        }
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>, version: Version) -> Result<RnoteDocument> {
+        let bytes = String::from_utf8(decompress_from_gzip(&bytes)?)?;
+
+        match version {
+            Version::V0_4 => Ok(RnoteDocument::V0_4(serde_json::from_str(bytes.as_str())?)),
+            Version::V0_5 => Ok(RnoteDocument::V0_5(serde_json::from_str(bytes.as_str())?)),
+        }
+    }
+
+    /// Loads a document from compressed bytes, detecting its version.
+    pub fn from_bytes_detect(bytes: Vec<u8>) -> Result<RnoteDocument> {
+        let json: serde_json::Value = serde_json::from_slice(&decompress_from_gzip(&bytes)?)?;
+
+        match Version::detect(&json)? {
+            Version::V0_4 => Ok(RnoteDocument::V0_4(serde_json::from_value(json)?)),
+            Version::V0_5 => Ok(RnoteDocument::V0_5(serde_json::from_value(json)?)),
+        }
+    }
+
+    /// Reads and loads a document from a file, detecting its version.
+    pub fn load(file: &Path) -> Result<RnoteDocument> {
+        let bytes =
+            fs::read(file).with_context(|| anyhow!("failed to read file '{}'", file.display()))?;
+
+        Self::from_bytes_detect(bytes)
+            .with_context(|| anyhow!("failed to load document '{}'", file.display()))
+    }
+
+    /// Upgrades the document to v0.5, which the document operations work on.
+    pub fn into_v0_5(self) -> rnotev0_5::RnotefileWrapper {
+        match self {
+            Self::V0_5(val) => val,
+            doc => match doc.into_version(Version::V0_5) {
+                Self::V0_5(val) => val,
+                _ => unreachable!(),
+            },
+        }
+    }
+
+    /// The store snapshot of the document. The strokes state of v0.4 documents is converted,
+    /// leaving out strokes which fail to convert.
+    pub fn store_snapshot(&self) -> Cow<'_, rnotev0_5::store::StoreSnapshot> {
+        match self {
+            Self::V0_4(val) => Cow::Owned(val.strokes_state.clone().into()),
+            Self::V0_5(val) => Cow::Borrowed(val.store_snapshot()),
+        }
+    }
+
+    /// Saves the document to a file.
+    pub fn save(&self, file: &Path) -> Result<()> {
+        let file_name = file
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        let bytes = compress_to_gzip(self.to_json(false)?.as_bytes(), &file_name)?;
+
+        fs::write(file, bytes).with_context(|| anyhow!("failed to write '{}'", file.display()))
+    }
+
+    pub fn into_version(mut self, version: Version) -> RnoteDocument {
+        let start_version = self.version();
+        let mut iter = Version::iter().skip_while(|&v| v != start_version);
+        iter.next();
+
+        for next_version in iter {
+            let curr_version = self.version();
+            match (self, version) {
+                (Self::V0_4(val), Version::V0_5) => {
+                    self = Self::V0_5(val.into());
+                }
+                _ => unimplemented!(
+                    "converting from {curr_version:?} to {version:?} is not implemented"
+                ),
+            }
+            if next_version == version {
+                break;
+            }
+        }
+        eprintln!(
+            "converted from {start_version:?} to {:?} (expected {version:?})",
+            self.version()
+        );
+        self
+    }
+
+    pub fn to_json(&self, pretty: bool) -> Result<String> {
+        fn serialize<T: Serialize>(val: &T, pretty: bool) -> Result<String> {
+            if pretty {
+                serde_json::to_string_pretty(val)
+            } else {
+                serde_json::to_string(val)
+            }
+            .map_err(Into::into)
+        }
+
+        match self {
+            Self::V0_4(val) => serialize(val, pretty),
+            Self::V0_5(val) => serialize(val, pretty),
+        }
+    }
+
+    /// Applies the normalization, if the document version supports it.
+    pub fn normalize(&mut self, normalize: &ops::normalize::Normalize) {
+        match self {
+            Self::V0_4(_) => {}
+            Self::V0_5(val) => {
+                normalize.apply(val);
+            }
+        }
+    }
+
+    pub fn into_output(self, output_type: OutputType, file_name: &str) -> Result<Vec<u8>> {
+        if let Some(version) = output_type.as_version() {
+            if version == self.version() {
+                compress_to_gzip(self.to_json(false)?.as_bytes(), file_name)
+            } else {
+                compress_to_gzip(
+                    self.into_version(version).to_json(false)?.as_bytes(),
+                    file_name,
+                )
+            }
+        } else {
+            match output_type {
+                OutputType::Json => Ok(self.to_json(false)?.into_bytes()),
+                OutputType::PrettyJson => Ok(self.to_json(true)?.into_bytes()),
+                _ => unreachable!(),
+            }
+        }
+    }
+}
+
+/// Decompress from gzip
+pub fn decompress_from_gzip(compressed: &[u8]) -> Result<Vec<u8>, anyhow::Error> {
+    let mut decoder = flate2::read::MultiGzDecoder::new(compressed);
+    let mut bytes: Vec<u8> = Vec::new();
+    decoder.read_to_end(&mut bytes)?;
+
+    Ok(bytes)
+}
+
+/// Compress bytes with gzip
+pub fn compress_to_gzip(to_compress: &[u8], file_name: &str) -> Result<Vec<u8>, anyhow::Error> {
+    let compressed_bytes = Vec::<u8>::new();
+
+    let mut encoder = flate2::GzBuilder::new()
+        .filename(file_name)
+        .write(compressed_bytes, flate2::Compression::default());
+
+    encoder.write_all(to_compress)?;
+
+    Ok(encoder.finish()?)
+}
@@ -1,150 +1,871 @@
-#![allow(dead_code)]
-
 use std::fs;
-use std::io::{Read, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Context, Result};
-use clap::{ArgEnum, Parser};
-use serde::Serialize;
-use strum::{EnumIter, IntoEnumIterator};
-
-mod rnotev0_4;
-mod rnotev0_5;
-mod slot;
-
-extern crate nalgebra as na;
-extern crate parry2d_f64 as p2d;
+use clap::{ArgEnum, Parser, Subcommand};
+use parry2d_f64::bounding_volume::AABB;
+use rnote_version_converter::rnotev0_4::PatternStyle;
+use rnote_version_converter::rnotev0_5::store::StrokeFilter;
+use rnote_version_converter::rnotev0_5::stroke::{StrokeType, StyleKind};
+use rnote_version_converter::rnotev0_5::{Color, Layout, Orientation, RnotefileWrapper};
+use rnote_version_converter::{ops, OutputType, RnoteDocument, Version};
 
 #[derive(Parser)]
+#[clap(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
+    #[clap(subcommand)]
+    command: Option<Command>,
+    #[clap(flatten)]
+    convert: ConvertArgs,
+}
+
+#[derive(clap::Args)]
+struct ConvertArgs {
     /// The file to convert.
-    file: PathBuf,
+    #[clap(required = true)]
+    file: Option<PathBuf>,
     /// The destination file or `<file>-upgraded.rnote` per default.
     dest_file: Option<PathBuf>,
     #[clap(short = 'i', long, arg_enum, default_value_t = Version::V0_4)]
     input_type: Version,
     #[clap(short = 'o', long, arg_enum, default_value_t = OutputType::Latest)]
     output_type: OutputType,
+    #[clap(flatten)]
+    normalize: NormalizeArgs,
+    /// Downscale bitmap images which are larger than needed to be displayed at the image dpi.
+    #[clap(long)]
+    downscale_images: bool,
+    /// The dpi for which the needed size of bitmap images is determined. Defaults to the dpi of the document format.
+    #[clap(long, allow_hyphen_values = true, parse(try_from_str = parse_positive))]
+    image_dpi: Option<f64>,
+    /// Warn about bitmap images with identical data, which the file formats store separately.
+    #[clap(long)]
+    warn_duplicate_images: bool,
+    /// Fit the document extents to the strokes and choose a page based layout if the strokes are placed on pages.
+    #[clap(long)]
+    fit_extents: bool,
+    /// Simplify the pen paths of brush strokes, keeping their deviation within the tolerance in document units.
+    #[clap(long, value_name = "TOLERANCE", allow_hyphen_values = true, parse(try_from_str = parse_non_negative))]
+    simplify: Option<f64>,
+    /// Assign stable seeds to rough and textured strokes without a seed, so they look the same at every load.
+    #[clap(long)]
+    stable_seeds: bool,
+    #[clap(flatten)]
+    format: FormatArgs,
 }
 
-#[derive(ArgEnum, Copy, Clone, Debug, EnumIter, PartialEq, Eq)]
-enum Version {
-    V0_4,
-    V0_5,
+#[derive(clap::Args)]
+struct FormatArgs {
+    /// The layout of the document.
+    #[clap(long, arg_enum)]
+    layout: Option<Layout>,
+    /// The page size of the format.
+    #[clap(long, arg_enum)]
+    page: Option<ops::format::PagePreset>,
+    /// The dpi of the format. The strokes are rescaled to keep their physical size.
+    #[clap(long)]
+    dpi: Option<f64>,
+    /// The orientation of the pages.
+    #[clap(long, arg_enum)]
+    orientation: Option<Orientation>,
+    /// The color of the page borders, `#rrggbb` or `#rrggbbaa`.
+    #[clap(long)]
+    border_color: Option<Color>,
+    /// Show the page borders, `true` or `false`.
+    #[clap(long)]
+    show_borders: Option<bool>,
 }
 
-#[derive(ArgEnum, Copy, Clone, Debug, EnumIter)]
-enum OutputType {
-    Json,
-    PrettyJson,
-    V0_4,
-    V0_5,
-    Latest,
+impl From<FormatArgs> for ops::format::FormatOverrides {
+    fn from(args: FormatArgs) -> Self {
+        Self {
+            layout: args.layout,
+            preset: args.page,
+            dpi: args.dpi,
+            orientation: args.orientation,
+            border_color: args.border_color,
+            show_borders: args.show_borders,
+        }
+    }
 }
 
-impl OutputType {
-    pub fn as_version(self) -> Option<Version> {
-        match self {
-            Self::V0_4 => Some(Version::V0_4),
-            Self::V0_5 | Self::Latest => Some(Version::V0_5),
-            _ => None,
+#[derive(clap::Args)]
+struct NormalizeArgs {
+    /// Keep the selection of the strokes instead of deselecting them.
+    #[clap(long)]
+    keep_selection: bool,
+    /// Renumber the chrono ordering of the strokes.
+    #[clap(long)]
+    reset_chrono: bool,
+    /// Remove trashed strokes and compact the store.
+    #[clap(long)]
+    purge_trashed: bool,
+}
+
+impl From<NormalizeArgs> for ops::normalize::Normalize {
+    fn from(args: NormalizeArgs) -> Self {
+        Self {
+            clear_selection: !args.keep_selection,
+            reset_chrono: args.reset_chrono,
+            purge_trashed: args.purge_trashed,
         }
     }
 }
 
-#[derive(Debug, Clone)]
-enum RnoteDocument {
-    V0_4(rnotev0_4::Sheet),
-    V0_5(rnotev0_5::RnotefileWrapper),
+#[derive(clap::Args)]
+struct SelectorArgs {
+    /// Only select strokes of this type. Can be given multiple times.
+    #[clap(long = "type", arg_enum)]
+    types: Vec<StrokeType>,
+    /// Only select strokes intersecting the rectangle, `x,y,width,height`.
+    #[clap(long, allow_hyphen_values = true, parse(try_from_str = parse_list))]
+    within: Option<[f64; 4]>,
+    /// Only select the selected strokes.
+    #[clap(long)]
+    selected: bool,
 }
 
-impl RnoteDocument {
-    pub fn version(&self) -> Version {
-        match self {
-            Self::V0_4(_) => Version::V0_4,
-            Self::V0_5(_) => Version::V0_5,
+impl From<SelectorArgs> for StrokeFilter {
+    fn from(args: SelectorArgs) -> Self {
+        let mut filter = StrokeFilter::default();
+        for stroke_type in args.types {
+            filter = filter.variant(stroke_type);
+        }
+        if let Some([x, y, width, height]) = args.within {
+            filter = filter.intersecting(AABB::new(
+                nalgebra::point![x, y],
+                nalgebra::point![x + width, y + height],
+            ));
+        }
+        if args.selected {
+            filter = filter.selected(true);
         }
+        filter
     }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Compare two documents and report the changed strokes.
+    Diff {
+        /// The original document.
+        a: PathBuf,
+        /// The changed document.
+        b: PathBuf,
+        #[clap(short, long, arg_enum, default_value_t = ReportFormat::Human)]
+        format: ReportFormat,
+    },
+    /// Merge multiple documents into one, placing them below each other.
+    Merge {
+        /// The documents to merge.
+        #[clap(required = true, min_values = 2)]
+        files: Vec<PathBuf>,
+        /// The destination file.
+        #[clap(short, long)]
+        output: PathBuf,
+        /// Take the format from this document instead of the first one.
+        #[clap(long)]
+        format_from: Option<PathBuf>,
+        /// Take the background from this document instead of the first one.
+        #[clap(long)]
+        background_from: Option<PathBuf>,
+        #[clap(flatten)]
+        normalize: NormalizeArgs,
+    },
+    /// Split a document with a page based layout into one document per page.
+    Split {
+        /// The document to split.
+        file: PathBuf,
+        /// How strokes straddling the boundary between pages are assigned.
+        #[clap(short, long, arg_enum, default_value_t = ops::split::StraddlePolicy::Center)]
+        straddle: ops::split::StraddlePolicy,
+        /// The directory of the page files, `<file>-page-<n>.rnote`. Defaults to the directory of the file.
+        #[clap(short, long)]
+        output_dir: Option<PathBuf>,
+        #[clap(flatten)]
+        normalize: NormalizeArgs,
+    },
+    /// Print information about a document and the size of its bitmap images.
+    Info {
+        /// The document.
+        file: PathBuf,
+        /// The dpi for which the needed size of bitmap images is determined. Defaults to the dpi of the document format.
+        #[clap(long, allow_hyphen_values = true, parse(try_from_str = parse_positive))]
+        image_dpi: Option<f64>,
+        #[clap(short, long, arg_enum, default_value_t = ReportFormat::Human)]
+        format: ReportFormat,
+    },
+    /// Remove trashed strokes and compact the store of a document.
+    Compact {
+        /// The document to compact.
+        file: PathBuf,
+        /// The destination file, or overwrite the document per default. Only v0.5 documents can be overwritten.
+        dest_file: Option<PathBuf>,
+    },
+    /// Extract the embedded images of a document, together with a manifest of their placement.
+    ExtractImages {
+        /// The document.
+        file: PathBuf,
+        /// The directory of the extracted images, `image-<slot>.png|svg` and `manifest.json`.
+        dir: PathBuf,
+    },
+    /// Set the background of one or many v0.5 documents, overwriting them.
+    SetBackground {
+        /// The documents.
+        #[clap(required = true)]
+        files: Vec<PathBuf>,
+        /// Only change documents with this background pattern.
+        #[clap(long, arg_enum)]
+        only_pattern: Option<PatternStyle>,
+        /// A color scheme preset for the background colors. Stroke colors with too little contrast are inverted.
+        #[clap(long, arg_enum)]
+        scheme: Option<ops::background::ColorScheme>,
+        /// The background color, `#rrggbb` or `#rrggbbaa`.
+        #[clap(long)]
+        color: Option<Color>,
+        /// The background pattern.
+        #[clap(long, arg_enum)]
+        pattern: Option<PatternStyle>,
+        /// The size of the pattern, `width,height`.
+        #[clap(long, parse(try_from_str = parse_list))]
+        pattern_size: Option<[f64; 2]>,
+        /// The color of the pattern, `#rrggbb` or `#rrggbbaa`.
+        #[clap(long)]
+        pattern_color: Option<Color>,
+    },
+    /// Write a printer friendly copy of a document, converting its colors and images to grayscale or an ink palette
+    /// and removing the background color and pattern.
+    PrintFriendly {
+        /// The document.
+        file: PathBuf,
+        /// The destination file.
+        dest_file: PathBuf,
+        /// Convert to the nearest color of this palette instead of grayscale, comma separated `#rrggbb` colors.
+        #[clap(long, use_value_delimiter = true)]
+        palette: Vec<Color>,
+        /// Remove the fill colors of shapes.
+        #[clap(long)]
+        remove_fills: bool,
+    },
+    /// Remap the pen pressures of all brush strokes through a curve, to normalize the pressure profiles of different
+    /// tablets.
+    RemapPressure {
+        /// The document.
+        file: PathBuf,
+        /// Raise the pressures to the power of gamma. Values below 1 make light strokes heavier.
+        #[clap(long)]
+        gamma: Option<f64>,
+        /// Map the pressure range `min,max` linearly to the full range.
+        #[clap(long, parse(try_from_str = parse_list))]
+        range: Option<[f64; 2]>,
+        /// Interpolate linearly between the points of a table, `<input>:<output>,<input>:<output>,..`.
+        #[clap(long, parse(try_from_str = ops::pressure::PressureCurve::parse_table))]
+        table: Option<ops::pressure::PressureCurve>,
+        /// The destination file, or overwrite the document per default. Only v0.5 documents can be overwritten.
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Convert the style of brush strokes, carrying over their width, colors and seed.
+    Restyle {
+        /// The document.
+        file: PathBuf,
+        /// The style to convert to.
+        #[clap(long, arg_enum)]
+        to: StyleKind,
+        /// Only convert brush strokes with this style.
+        #[clap(long, arg_enum)]
+        from: Option<StyleKind>,
+        #[clap(flatten)]
+        selector: SelectorArgs,
+        /// The destination file, or overwrite the document per default. Only v0.5 documents can be overwritten.
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Simplify the pen paths of all brush strokes, reducing the number of elements.
+    Simplify {
+        /// The document.
+        file: PathBuf,
+        /// The maximum deviation of the simplified paths in document units.
+        #[clap(long, default_value_t = 0.5, allow_hyphen_values = true, parse(try_from_str = parse_non_negative))]
+        tolerance: f64,
+        /// Only consider the positions of the elements, not their pressure.
+        #[clap(long)]
+        ignore_pressure: bool,
+        /// The destination file, or overwrite the document per default. Only v0.5 documents can be overwritten.
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Recolor a document with a color mapping table.
+    Recolor {
+        /// The document.
+        file: PathBuf,
+        /// A color mapping, `<from>=<to>` with the colors as `#rrggbb` or `#rrggbbaa`. Can be given multiple times.
+        #[clap(long = "map")]
+        mappings: Vec<ops::recolor::ColorMapping>,
+        /// A file with one color mapping per line, in the same form as `--map`.
+        #[clap(long)]
+        map_file: Option<PathBuf>,
+        /// Map colors to the nearest mapping within this euclidean distance in the RGBA space (channels between 0.0
+        /// and 1.0), instead of only exact matches.
+        #[clap(long, default_value_t = 0.0, allow_hyphen_values = true, parse(try_from_str = parse_non_negative))]
+        tolerance: f64,
+        /// The destination file, or overwrite the document per default. Only v0.5 documents can be overwritten.
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Crop a document to a region, removing the strokes outside of it and moving the region to the origin.
+    Crop {
+        /// The document.
+        file: PathBuf,
+        /// The region, `x,y,width,height`.
+        #[clap(long, allow_hyphen_values = true, parse(try_from_str = parse_list))]
+        rect: [f64; 4],
+        /// Clip the pen paths of brush strokes at the boundary of the region.
+        #[clap(long)]
+        clip: bool,
+        /// The destination file, or overwrite the document per default. Only v0.5 documents can be overwritten.
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Transform all or the selected strokes of a document. The transformations are applied in the order mirror,
+    /// scale, rotate, translate.
+    Transform {
+        /// The document.
+        file: PathBuf,
+        /// Mirror along the axis.
+        #[clap(long, arg_enum)]
+        mirror: Option<ops::transform::MirrorAxis>,
+        /// Scale by the factor, or the factors `x,y`.
+        #[clap(long, allow_hyphen_values = true, parse(try_from_str = parse_scale))]
+        scale: Option<[f64; 2]>,
+        /// Rotate clockwise by the angle in degrees.
+        #[clap(long, allow_hyphen_values = true)]
+        rotate: Option<f64>,
+        /// Translate by the offset, `x,y`.
+        #[clap(long, allow_hyphen_values = true, parse(try_from_str = parse_list))]
+        translate: Option<[f64; 2]>,
+        /// The center of mirroring, scaling and rotating, `x,y`. Defaults to the center of the selected strokes.
+        #[clap(long, allow_hyphen_values = true, parse(try_from_str = parse_list))]
+        center: Option<[f64; 2]>,
+        /// Scale the stroke widths with the strokes.
+        #[clap(long)]
+        scale_stroke_widths: bool,
+        #[clap(flatten)]
+        selector: SelectorArgs,
+        /// Fit the document extents to the strokes afterwards, keeping the layout.
+        #[clap(long)]
+        fit_extents: bool,
+        /// The destination file, or overwrite the document per default. Only v0.5 documents can be overwritten.
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Insert an image (png, jpeg, svg, ..) into a document, or replace an existing image.
+    InsertImage {
+        /// The document.
+        file: PathBuf,
+        /// The image to insert.
+        image: PathBuf,
+        /// The position of the upper left corner of the image, `x,y`.
+        #[clap(long, allow_hyphen_values = true, parse(try_from_str = parse_list))]
+        at: Option<[f64; 2]>,
+        /// The width of the image. The height is determined by the aspect ratio.
+        #[clap(long)]
+        width: Option<f64>,
+        /// Replace the image in this slot, keeping its position and width unless overridden.
+        #[clap(long)]
+        replace: Option<usize>,
+        /// The destination file, or overwrite the document per default. Only v0.5 documents can be overwritten.
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+}
 
-    pub fn from_bytes(bytes: Vec<u8>, version: Version) -> Result<RnoteDocument> {
-        let bytes = String::from_utf8(decompress_from_gzip(&bytes)?)?;
+#[derive(ArgEnum, Copy, Clone, Debug)]
+enum ReportFormat {
+    Human,
+    Json,
+}
+
+fn main() -> Result<()> {
+    let Args { command, convert } = Args::parse();
 
-        match version {
-            Version::V0_4 => Ok(RnoteDocument::V0_4(serde_json::from_str(bytes.as_str())?)),
-            Version::V0_5 => Ok(RnoteDocument::V0_5(serde_json::from_str(bytes.as_str())?)),
+    match command {
+        None => run_convert(convert),
+        Some(Command::Diff { a, b, format }) => {
+            let a = RnoteDocument::load(&a)?.into_v0_5();
+            let b = RnoteDocument::load(&b)?.into_v0_5();
+            let diff = ops::diff::DocumentDiff::new(&a, &b);
+
+            match format {
+                ReportFormat::Human => print!("{diff}"),
+                ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&diff)?),
+            }
+            Ok(())
         }
-    }
+        Some(Command::Merge {
+            files,
+            output,
+            format_from,
+            background_from,
+            normalize,
+        }) => {
+            let options = ops::merge::MergeOptions {
+                format: format_from
+                    .map(|file| {
+                        anyhow::Ok(RnoteDocument::load(&file)?.into_v0_5().document().format)
+                    })
+                    .transpose()?,
+                background: background_from
+                    .map(|file| {
+                        anyhow::Ok(
+                            RnoteDocument::load(&file)?
+                                .into_v0_5()
+                                .document()
+                                .background
+                                .clone(),
+                        )
+                    })
+                    .transpose()?,
+            };
+            let documents = files
+                .iter()
+                .map(|file| Ok(RnoteDocument::load(file)?.into_v0_5()))
+                .collect::<Result<Vec<_>>>()?;
+
+            let mut merged = RnoteDocument::V0_5(ops::merge::merge(documents, &options)?);
+            merged.normalize(&normalize.into());
+            merged.save(&output)
+        }
+        Some(Command::Split {
+            file,
+            straddle,
+            output_dir,
+            normalize,
+        }) => {
+            let pages = ops::split::split(&RnoteDocument::load(&file)?.into_v0_5(), straddle)?;
+            let output_dir = output_dir
+                .or_else(|| file.parent().map(Path::to_path_buf))
+                .unwrap_or_default();
+            let stem = file
+                .file_stem()
+                .expect("<file> must be a file")
+                .to_string_lossy();
+
+            let normalize = normalize.into();
+
+            for (i, page) in pages.into_iter().enumerate() {
+                let page_file = output_dir.join(format!("{stem}-page-{}.rnote", i + 1));
+                let mut page = RnoteDocument::V0_5(page);
+                page.normalize(&normalize);
+                page.save(&page_file)?;
+                println!("{}", page_file.display());
+            }
+            Ok(())
+        }
+        Some(Command::Info {
+            file,
+            image_dpi,
+            format,
+        }) => {
+            let info =
+                ops::info::DocumentInfo::new(&RnoteDocument::load(&file)?.into_v0_5(), image_dpi);
+
+            match format {
+                ReportFormat::Human => print!("{info}"),
+                ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&info)?),
+            }
+            Ok(())
+        }
+        Some(Command::Compact { file, dest_file }) => {
+            let (mut document, dest_file) = load_for_edit(&file, dest_file)?;
+            let report = ops::compact::compact(&mut document)?;
+            RnoteDocument::V0_5(document).save(&dest_file)?;
+
+            println!(
+                "removed {} trashed strokes and {} vacant slots, saved {} bytes ({} -> {})",
+                report.removed_trashed,
+                report.removed_vacant,
+                report.bytes_saved(),
+                report.bytes_before,
+                report.bytes_after
+            );
+            Ok(())
+        }
+        Some(Command::ExtractImages { file, dir }) => {
+            let manifest = ops::extract::extract_images(&RnoteDocument::load(&file)?.into_v0_5())?;
+
+            fs::create_dir_all(&dir)
+                .with_context(|| anyhow!("failed to create directory '{}'", dir.display()))?;
+            for image in manifest.images.iter() {
+                let image_file = dir.join(&image.file);
+                fs::write(&image_file, &image.bytes)
+                    .with_context(|| anyhow!("failed to write '{}'", image_file.display()))?;
+                println!("{}", image_file.display());
+            }
+
+            let manifest_file = dir.join(ops::extract::MANIFEST_FILE_NAME);
+            fs::write(&manifest_file, serde_json::to_string_pretty(&manifest)?)
+                .with_context(|| anyhow!("failed to write '{}'", manifest_file.display()))?;
+            Ok(())
+        }
+        Some(Command::SetBackground {
+            files,
+            only_pattern,
+            scheme,
+            color,
+            pattern,
+            pattern_size,
+            pattern_color,
+        }) => {
+            let options = ops::background::BackgroundOptions {
+                only_pattern,
+                scheme,
+                color,
+                pattern,
+                pattern_size: pattern_size.map(|[width, height]| nalgebra::vector![width, height]),
+                pattern_color,
+            };
 
-    pub fn into_version(mut self, version: Version) -> RnoteDocument {
-        let start_version = self.version();
-        let mut iter = Version::iter().skip_while(|&v| v != start_version);
-        iter.next();
+            for file in files.iter() {
+                let (mut document, dest_file) = load_for_edit(file, None)?;
+                let report = ops::background::set_background(&mut document, &options);
 
-        for next_version in iter {
-            let curr_version = self.version();
-            match (self, version) {
-                (Self::V0_4(val), Version::V0_5) => {
-                    self = Self::V0_5(val.into());
+                if report.changed || report.inverted_colors > 0 {
+                    RnoteDocument::V0_5(document).save(&dest_file)?;
+                    println!(
+                        "{}: changed the background, inverted {} stroke colors",
+                        file.display(),
+                        report.inverted_colors
+                    );
+                } else {
+                    println!("{}: unchanged", file.display());
                 }
-                _ => unimplemented!(
-                    "converting from {curr_version:?} to {version:?} is not implemented"
-                ),
             }
-            if next_version == version {
-                break;
+            Ok(())
+        }
+        Some(Command::PrintFriendly {
+            file,
+            dest_file,
+            palette,
+            remove_fills,
+        }) => {
+            let options = ops::print::PrintOptions {
+                ink: if palette.is_empty() {
+                    ops::print::Ink::Grayscale
+                } else {
+                    ops::print::Ink::Palette(palette)
+                },
+                remove_fills,
+            };
+
+            let mut document = RnoteDocument::load(&file)?.into_v0_5();
+            let report = ops::print::print_friendly(&mut document, &options)?;
+            RnoteDocument::V0_5(document).save(&dest_file)?;
+
+            println!(
+                "converted {} colors and {} bitmap images, removed {} fills",
+                report.colors, report.bitmap_images, report.removed_fills
+            );
+            if report.vector_images > 0 {
+                eprintln!(
+                    "the colors of {} vector images were not converted",
+                    report.vector_images
+                );
             }
+            Ok(())
         }
-        eprintln!(
-            "converted from {start_version:?} to {:?} (expected {version:?})",
-            self.version()
-        );
-        self
-    }
+        Some(Command::RemapPressure {
+            file,
+            gamma,
+            range,
+            table,
+            output,
+        }) => {
+            use ops::pressure::PressureCurve;
 
-    pub fn to_json(&self, pretty: bool) -> Result<String> {
-        fn serialize<T: Serialize>(val: &T, pretty: bool) -> Result<String> {
-            if pretty {
-                serde_json::to_string_pretty(val)
-            } else {
-                serde_json::to_string(val)
+            let curve = match (gamma, range, table) {
+                (Some(gamma), None, None) => PressureCurve::Gamma(gamma),
+                (None, Some([min, max]), None) => PressureCurve::LinearRange { min, max },
+                (None, None, Some(table)) => table,
+                _ => {
+                    return Err(anyhow!(
+                        "expected exactly one of `--gamma`, `--range` or `--table`"
+                    ))
+                }
+            };
+
+            let (mut document, dest_file) = load_for_edit(&file, output)?;
+            let report = ops::pressure::remap_pressure(&mut document, &curve)?;
+            RnoteDocument::V0_5(document).save(&dest_file)?;
+
+            println!(
+                "remapped the pressures of {} brush strokes, changed {} elements",
+                report.strokes, report.changed_elements
+            );
+            Ok(())
+        }
+        Some(Command::Restyle {
+            file,
+            to,
+            from,
+            selector,
+            output,
+        }) => {
+            let (mut document, dest_file) = load_for_edit(&file, output)?;
+            let restyled =
+                ops::restyle::restyle(&mut document, to, from, &StrokeFilter::from(selector));
+            RnoteDocument::V0_5(document).save(&dest_file)?;
+
+            println!("restyled {restyled} brush strokes");
+            Ok(())
+        }
+        Some(Command::Simplify {
+            file,
+            tolerance,
+            ignore_pressure,
+            output,
+        }) => {
+            let (mut document, dest_file) = load_for_edit(&file, output)?;
+            let report = ops::simplify::simplify(&mut document, tolerance, ignore_pressure);
+            RnoteDocument::V0_5(document).save(&dest_file)?;
+
+            println!(
+                "simplified {} brush strokes from {} to {} elements",
+                report.strokes, report.elements_before, report.elements_after
+            );
+            Ok(())
+        }
+        Some(Command::Recolor {
+            file,
+            mut mappings,
+            map_file,
+            tolerance,
+            output,
+        }) => {
+            if let Some(map_file) = map_file {
+                let table = fs::read_to_string(&map_file).with_context(|| {
+                    anyhow!("failed to read the mapping file '{}'", map_file.display())
+                })?;
+                mappings.extend(ops::recolor::ColorTable::parse(&table, tolerance)?.mappings);
+            }
+            if mappings.is_empty() {
+                return Err(anyhow!(
+                    "no color mappings given, use `--map` or `--map-file`"
+                ));
             }
-            .map_err(Into::into)
+            let table = ops::recolor::ColorTable {
+                mappings,
+                tolerance,
+            };
+
+            let (mut document, dest_file) = load_for_edit(&file, output)?;
+            let report = ops::recolor::recolor(&mut document, &table);
+            RnoteDocument::V0_5(document).save(&dest_file)?;
+
+            println!(
+                "changed {} colors ({} in strokes, {} in the background, {} in the format)",
+                report.total(),
+                report.strokes,
+                report.background,
+                report.format
+            );
+            Ok(())
         }
+        Some(Command::Crop {
+            file,
+            rect: [x, y, width, height],
+            clip,
+            output,
+        }) => {
+            if width <= 0.0 || height <= 0.0 {
+                return Err(anyhow!("the region must have a positive width and height"));
+            }
+            let region = AABB::new(
+                nalgebra::point![x, y],
+                nalgebra::point![x + width, y + height],
+            );
+
+            let (mut document, dest_file) = load_for_edit(&file, output)?;
+            let report = ops::crop::crop(&mut document, region, clip);
+            RnoteDocument::V0_5(document).save(&dest_file)?;
 
-        match self {
-            Self::V0_4(val) => serialize(val, pretty),
-            Self::V0_5(val) => serialize(val, pretty),
+            println!(
+                "kept {} strokes ({} clipped), removed {} strokes",
+                report.kept, report.clipped, report.removed
+            );
+            Ok(())
         }
-    }
+        Some(Command::Transform {
+            file,
+            mirror,
+            scale,
+            rotate,
+            translate,
+            center,
+            scale_stroke_widths,
+            selector,
+            fit_extents,
+            output,
+        }) => {
+            use ops::transform::Transformation;
 
-    pub fn into_output(self, output_type: OutputType, file_name: &str) -> Result<Vec<u8>> {
-        if let Some(version) = output_type.as_version() {
-            if version == self.version() {
-                compress_to_gzip(self.to_json(false)?.as_bytes(), file_name)
-            } else {
-                compress_to_gzip(
-                    self.into_version(version).to_json(false)?.as_bytes(),
-                    file_name,
-                )
+            let center = center.map(|[x, y]| nalgebra::point![x, y]);
+            let transformations: Vec<Transformation> = [
+                mirror.map(|axis| Transformation::Mirror { axis, center }),
+                scale.map(|[x, y]| Transformation::Scale {
+                    factors: nalgebra::vector![x, y],
+                    center,
+                }),
+                rotate.map(|degrees| Transformation::Rotate {
+                    angle: degrees.to_radians(),
+                    center,
+                }),
+                translate.map(|[x, y]| Transformation::Translate(nalgebra::vector![x, y])),
+            ]
+            .into_iter()
+            .flatten()
+            .collect();
+            if transformations.is_empty() {
+                return Err(anyhow!(
+                    "no transformation given, use `--mirror`, `--scale`, `--rotate` or `--translate`"
+                ));
             }
-        } else {
-            match output_type {
-                OutputType::Json => Ok(self.to_json(false)?.into_bytes()),
-                OutputType::PrettyJson => Ok(self.to_json(true)?.into_bytes()),
-                _ => unreachable!(),
+            let filter = StrokeFilter::from(selector);
+
+            let (mut document, dest_file) = load_for_edit(&file, output)?;
+            let transformed = ops::transform::transform(
+                &mut document,
+                &transformations,
+                &filter,
+                scale_stroke_widths,
+            )?;
+            if fit_extents {
+                let layout = document.document().layout();
+                ops::fit::fit_extents(&mut document, Some(layout));
             }
+            RnoteDocument::V0_5(document).save(&dest_file)?;
+
+            println!("transformed {transformed} strokes");
+            Ok(())
+        }
+        Some(Command::InsertImage {
+            file,
+            image,
+            at,
+            width,
+            replace,
+            output,
+        }) => {
+            let (mut document, dest_file) = load_for_edit(&file, output)?;
+            let bytes = fs::read(&image)
+                .with_context(|| anyhow!("failed to read image '{}'", image.display()))?;
+
+            let svg_data;
+            let image_data = if image
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"))
+            {
+                svg_data = String::from_utf8(bytes)?;
+                ops::insert::ImageData::Svg(&svg_data)
+            } else {
+                let format = image::ImageFormat::from_path(&image)
+                    .or_else(|_| image::guess_format(&bytes))
+                    .with_context(|| anyhow!("unknown image format of '{}'", image.display()))?;
+                ops::insert::ImageData::Bitmap(&bytes, format)
+            };
+            let options = ops::insert::InsertOptions {
+                position: at.map(|[x, y]| nalgebra::vector![x, y]),
+                width,
+                replace,
+            };
+
+            let index = ops::insert::insert_image(&mut document, image_data, &options)?;
+            RnoteDocument::V0_5(document).save(&dest_file)?;
+
+            println!("inserted image into slot {index}");
+            Ok(())
         }
     }
 }
 
-fn main() -> Result<()> {
-    let Args {
+/// Loads the document for a subcommand which modifies it. Returns it with the file it is saved to, the output or
+/// the document itself.
+///
+/// The subcommands upgrade documents to v0.5, which older versions of Rnote can't open. So documents of older versions
+/// are not overwritten, they require an explicit output.
+fn load_for_edit(file: &Path, output: Option<PathBuf>) -> Result<(RnotefileWrapper, PathBuf)> {
+    let document = RnoteDocument::load(file)?;
+    let dest_file = match output {
+        Some(output) => output,
+        None if document.version() == Version::V0_5 => file.to_path_buf(),
+        None => {
+            return Err(anyhow!(
+                "'{}' is not a v0.5 document and would be upgraded to v0.5 when it is overwritten, \
+                which older versions of Rnote can't open. Convert it first or pass a destination file",
+                file.display()
+            ))
+        }
+    };
+
+    Ok((document.into_v0_5(), dest_file))
+}
+
+/// Parses a comma separated list of numbers, e.g. `x,y`
+fn parse_list<const N: usize>(s: &str) -> Result<[f64; N]> {
+    let values = s
+        .split(',')
+        .map(|value| value.trim().parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    values
+        .try_into()
+        .map_err(|_| anyhow!("expected {N} comma separated numbers"))
+}
+
+/// Parses a positive, finite number
+fn parse_positive(s: &str) -> Result<f64> {
+    let value = s.trim().parse::<f64>()?;
+    if value.is_finite() && value > 0.0 {
+        Ok(value)
+    } else {
+        Err(anyhow!("expected a positive number, got {value}"))
+    }
+}
+
+/// Parses a non-negative, finite number
+fn parse_non_negative(s: &str) -> Result<f64> {
+    let value = s.trim().parse::<f64>()?;
+    if value.is_finite() && value >= 0.0 {
+        Ok(value)
+    } else {
+        Err(anyhow!("expected a non-negative number, got {value}"))
+    }
+}
+
+/// Parses a uniform scale factor, or the factors `x,y`
+fn parse_scale(s: &str) -> Result<[f64; 2]> {
+    match s.trim().parse::<f64>() {
+        Ok(factor) => Ok([factor, factor]),
+        Err(_) => parse_list(s),
+    }
+}
+
+fn run_convert(args: ConvertArgs) -> Result<()> {
+    let ConvertArgs {
         file,
         dest_file,
         input_type,
         output_type,
-    } = Args::parse();
+        normalize,
+        downscale_images,
+        image_dpi,
+        warn_duplicate_images,
+        fit_extents,
+        simplify,
+        stable_seeds,
+        format,
+    } = args;
+    let file = file.expect("<file> is required");
 
     let dest_file = dest_file.unwrap_or_else(|| {
         let mut src_filename = file
@@ -162,7 +883,113 @@ fn main() -> Result<()> {
     let bytes =
         fs::read(&file).with_context(|| anyhow!("failed to read file '{}'", file.display()))?;
 
-    let output_bytes = RnoteDocument::from_bytes(bytes, input_type)?.into_output(
+    let mut document = RnoteDocument::from_bytes(bytes, input_type)?;
+    if let Some(version) = output_type.as_version() {
+        if version != document.version() {
+            document = document.into_version(version);
+        }
+    }
+    document.normalize(&normalize.into());
+    let format: ops::format::FormatOverrides = format.into();
+    if !format.is_empty() {
+        match &mut document {
+            RnoteDocument::V0_5(val) => {
+                let scale = ops::format::apply_format(val, &format)?;
+                if scale != 1.0 {
+                    eprintln!("rescaled the strokes by {scale} for the changed dpi");
+                }
+            }
+            RnoteDocument::V0_4(_) => {
+                eprintln!("overriding the layout and format is only supported for v0.5 output")
+            }
+        }
+    }
+    if fit_extents {
+        match &mut document {
+            RnoteDocument::V0_5(val) => {
+                let report = ops::fit::fit_extents(val, format.layout);
+                eprintln!(
+                    "fitted the document extents to {} x {} with the {:?} layout (was {} x {}, {:?} layout)",
+                    report.bounds_after.extents()[0],
+                    report.bounds_after.extents()[1],
+                    report.layout_after,
+                    report.bounds_before.extents()[0],
+                    report.bounds_before.extents()[1],
+                    report.layout_before
+                );
+                if report.offset.iter().any(|offset| *offset != 0.0) {
+                    eprintln!(
+                        "moved the strokes by {}, {} onto the pages",
+                        report.offset[0], report.offset[1]
+                    );
+                }
+            }
+            RnoteDocument::V0_4(_) => {
+                eprintln!("fitting the document extents is only supported for v0.5 output")
+            }
+        }
+    }
+    if let Some(tolerance) = simplify {
+        match &mut document {
+            RnoteDocument::V0_5(val) => {
+                let report = ops::simplify::simplify(val, tolerance, false);
+                eprintln!(
+                    "simplified {} brush strokes from {} to {} elements",
+                    report.strokes, report.elements_before, report.elements_after
+                );
+            }
+            RnoteDocument::V0_4(_) => {
+                eprintln!("simplifying brush strokes is only supported for v0.5 output")
+            }
+        }
+    }
+    if stable_seeds {
+        match &mut document {
+            RnoteDocument::V0_5(val) => {
+                let seeded = ops::seeds::assign_stable_seeds(val)?;
+                eprintln!("assigned stable seeds to {seeded} strokes");
+            }
+            RnoteDocument::V0_4(_) => {
+                eprintln!("assigning stable seeds is only supported for v0.5 output")
+            }
+        }
+    }
+    if downscale_images {
+        match &mut document {
+            RnoteDocument::V0_5(val) => {
+                for image in ops::images::downscale_images(val, image_dpi)? {
+                    let (width, height) = image.downscaled_size();
+                    eprintln!(
+                        "downscaled image in slot {} from {} x {} px to {width} x {height} px",
+                        image.index, image.pixel_width, image.pixel_height
+                    );
+                }
+            }
+            RnoteDocument::V0_4(_) => {
+                eprintln!("downscaling images is only supported for v0.5 output")
+            }
+        }
+    }
+
+    if warn_duplicate_images {
+        match &document {
+            RnoteDocument::V0_5(val) => {
+                let redundant_bytes = ops::images::find_duplicate_images(val)
+                    .iter()
+                    .map(ops::images::DuplicateImages::redundant_bytes)
+                    .sum::<usize>();
+                if redundant_bytes > 0 {
+                    eprintln!(
+                        "{redundant_bytes} B of duplicate images are stored separately, the file formats can not share image data"
+                    );
+                }
+            }
+            RnoteDocument::V0_4(_) => {
+                eprintln!("detecting duplicate images is only supported for v0.5 output")
+            }
+        }
+    }
+    let output_bytes = document.into_output(
         output_type,
         &dest_file.file_name().unwrap().to_string_lossy(),
     )?;
@@ -174,25 +1001,3 @@ fn main() -> Result<()> {
 
     Ok(())
 }
-
-/// Decompress from gzip
-fn decompress_from_gzip(compressed: &[u8]) -> Result<Vec<u8>, anyhow::Error> {
-    let mut decoder = flate2::read::MultiGzDecoder::new(compressed);
-    let mut bytes: Vec<u8> = Vec::new();
-    decoder.read_to_end(&mut bytes)?;
-
-    Ok(bytes)
-}
-
-/// Compress bytes with gzip
-fn compress_to_gzip(to_compress: &[u8], file_name: &str) -> Result<Vec<u8>, anyhow::Error> {
-    let compressed_bytes = Vec::<u8>::new();
-
-    let mut encoder = flate2::GzBuilder::new()
-        .filename(file_name)
-        .write(compressed_bytes, flate2::Compression::default());
-
-    encoder.write_all(to_compress)?;
-
-    Ok(encoder.finish()?)
-}
@@ -0,0 +1,40 @@
+/// Restyling the background of documents
+pub mod background;
+/// Purging trashed strokes and compacting the store snapshot
+pub mod compact;
+/// Cropping documents to a region
+pub mod crop;
+/// Structural diff between two documents
+pub mod diff;
+/// Extracting embedded images from documents
+pub mod extract;
+/// Fitting the document extents to the strokes
+pub mod fit;
+/// Overriding the layout and format of documents
+pub mod format;
+/// Size analysis and downscaling of bitmap images
+pub mod images;
+/// Information about documents
+pub mod info;
+/// Inserting and replacing images in documents
+pub mod insert;
+/// Merging multiple documents into one
+pub mod merge;
+/// Removing transient editor state from documents
+pub mod normalize;
+/// Remapping the pen pressures of brush strokes
+pub mod pressure;
+/// Converting documents into printer friendly copies
+pub mod print;
+/// Remapping the colors of documents
+pub mod recolor;
+/// Converting brush strokes between styles
+pub mod restyle;
+/// Assigning stable seeds to rough and textured strokes
+pub mod seeds;
+/// Simplifying the pen paths of brush strokes
+pub mod simplify;
+/// Splitting documents into one document per page
+pub mod split;
+/// Geometric transformations of strokes
+pub mod transform;
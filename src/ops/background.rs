@@ -0,0 +1,218 @@
+use clap::ArgEnum;
+use serde::Serialize;
+
+use crate::rnotev0_4::{Background, PatternStyle};
+use crate::rnotev0_5::{Color, RnotefileWrapper};
+
+/// Color scheme presets for the background
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ColorScheme {
+    /// A dark background. Dark stroke colors are inverted
+    Dark,
+    /// A light background. Light stroke colors are inverted
+    Light,
+}
+
+impl ColorScheme {
+    /// The background color of the scheme
+    pub fn background_color(self) -> Color {
+        match self {
+            Self::Dark => Color {
+                r: 0.12,
+                g: 0.12,
+                b: 0.13,
+                a: 1.0,
+            },
+            Self::Light => Background::default().color,
+        }
+    }
+
+    /// The pattern color of the scheme
+    pub fn pattern_color(self) -> Color {
+        match self {
+            Self::Dark => Color {
+                r: 0.25,
+                g: 0.3,
+                b: 0.38,
+                a: 1.0,
+            },
+            Self::Light => Background::default().pattern_color,
+        }
+    }
+
+    /// Returns true if the color has too little contrast to the background of the scheme
+    pub fn lacks_contrast(self, color: &Color) -> bool {
+        match self {
+            Self::Dark => color.lightness() < 0.5,
+            Self::Light => color.lightness() > 0.5,
+        }
+    }
+}
+
+/// The changes to the background. Unset fields are kept.
+#[derive(Debug, Clone, Default)]
+pub struct BackgroundOptions {
+    /// Only change documents with this background pattern
+    pub only_pattern: Option<PatternStyle>,
+    /// The color scheme preset, which sets the colors of the background and inverts stroke colors for contrast.
+    /// Explicitly set colors take precedence.
+    pub scheme: Option<ColorScheme>,
+    pub color: Option<Color>,
+    pub pattern: Option<PatternStyle>,
+    pub pattern_size: Option<na::Vector2<f64>>,
+    pub pattern_color: Option<Color>,
+}
+
+/// The result of setting the background
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct BackgroundReport {
+    /// Whether the background was changed
+    pub changed: bool,
+    /// The number of stroke colors which were inverted
+    pub inverted_colors: usize,
+}
+
+/// Sets the background of the document. Documents whose pattern doesn't match `only_pattern` are left unchanged.
+pub fn set_background(
+    document: &mut RnotefileWrapper,
+    options: &BackgroundOptions,
+) -> BackgroundReport {
+    let background = &mut document.document_mut().background;
+    if options
+        .only_pattern
+        .is_some_and(|pattern| pattern != background.pattern)
+    {
+        return BackgroundReport::default();
+    }
+
+    let old_background = background.clone();
+    if let Some(scheme) = options.scheme {
+        background.color = scheme.background_color();
+        background.pattern_color = scheme.pattern_color();
+    }
+    if let Some(color) = options.color {
+        background.color = color;
+    }
+    if let Some(pattern) = options.pattern {
+        background.pattern = pattern;
+    }
+    if let Some(pattern_size) = options.pattern_size {
+        background.pattern_size = pattern_size;
+    }
+    if let Some(pattern_color) = options.pattern_color {
+        background.pattern_color = pattern_color;
+    }
+    let changed = *background != old_background;
+
+    let mut inverted_colors = 0;
+    if let Some(scheme) = options.scheme {
+        for color in document
+            .store_snapshot_mut()
+            .strokes_mut()
+            .filter_map(|stroke| stroke.style_mut())
+            .flat_map(|style| style.colors_mut())
+            .filter(|color| scheme.lacks_contrast(color))
+        {
+            *color = color.with_inverted_lightness();
+            inverted_colors += 1;
+        }
+    }
+
+    BackgroundReport {
+        changed,
+        inverted_colors,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rnotev0_5::stroke::{Stroke, Style};
+    use crate::rnotev0_5::tests::line_stroke;
+    use crate::rnotev0_5::{DocumentBuilder, Format, Layout};
+
+    /// A document with the default background and a black and a white line
+    fn document() -> RnotefileWrapper {
+        let mut builder =
+            DocumentBuilder::new(Format::default(), Layout::Infinite, Background::default());
+        builder.add_stroke(line_stroke(na::vector![0.0, 0.0], na::vector![10.0, 0.0]));
+        let mut white = line_stroke(na::vector![0.0, 10.0], na::vector![10.0, 10.0]);
+        if let Some(Style::Smooth(options)) = white.style_mut() {
+            options.stroke_color = Some(Color::WHITE);
+        }
+        builder.add_stroke(white);
+        builder.build()
+    }
+
+    fn stroke_colors(document: &RnotefileWrapper) -> Vec<Option<Color>> {
+        document
+            .strokes()
+            .map(|(_, stroke, ..)| match stroke {
+                Stroke::BrushStroke(brushstroke) => match &brushstroke.style {
+                    Style::Smooth(options) => options.stroke_color,
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn dark_scheme_inverts_dark_stroke_colors() {
+        let mut document = document();
+        let options = BackgroundOptions {
+            scheme: Some(ColorScheme::Dark),
+            ..Default::default()
+        };
+
+        let report = set_background(&mut document, &options);
+
+        assert!(report.changed);
+        assert_eq!(report.inverted_colors, 1);
+        let background = &document.document().background;
+        assert_eq!(background.color, ColorScheme::Dark.background_color());
+        assert_eq!(background.pattern_color, ColorScheme::Dark.pattern_color());
+        assert_eq!(
+            stroke_colors(&document),
+            vec![Some(Color::WHITE), Some(Color::WHITE)]
+        );
+    }
+
+    #[test]
+    fn explicit_colors_take_precedence_over_the_scheme() {
+        let mut document = document();
+        let options = BackgroundOptions {
+            scheme: Some(ColorScheme::Dark),
+            color: Some(Color::BLACK),
+            pattern: Some(PatternStyle::Grid),
+            ..Default::default()
+        };
+
+        set_background(&mut document, &options);
+
+        let background = &document.document().background;
+        assert_eq!(background.color, Color::BLACK);
+        assert_eq!(background.pattern, PatternStyle::Grid);
+        assert_eq!(background.pattern_color, ColorScheme::Dark.pattern_color());
+    }
+
+    #[test]
+    fn other_patterns_are_left_unchanged() {
+        let mut document = document();
+        let options = BackgroundOptions {
+            only_pattern: Some(PatternStyle::Lines),
+            scheme: Some(ColorScheme::Dark),
+            ..Default::default()
+        };
+
+        let report = set_background(&mut document, &options);
+
+        assert!(!report.changed);
+        assert_eq!(report.inverted_colors, 0);
+        assert_eq!(document.document().background, Background::default());
+        assert_eq!(
+            stroke_colors(&document),
+            vec![Some(Color::BLACK), Some(Color::WHITE)]
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use serde::Serialize;
+
+use crate::rnotev0_5::RnotefileWrapper;
+
+/// The result of compacting a document
+#[derive(Debug, Clone, Serialize)]
+pub struct CompactReport {
+    /// The number of removed trashed strokes
+    pub removed_trashed: usize,
+    /// The number of removed vacant slots
+    pub removed_vacant: usize,
+    /// The size of the compressed document before compacting
+    pub bytes_before: usize,
+    /// The size of the compressed document after compacting
+    pub bytes_after: usize,
+}
+
+impl CompactReport {
+    /// The number of bytes saved by compacting. Negative if the document grew.
+    pub fn bytes_saved(&self) -> i64 {
+        self.bytes_before as i64 - self.bytes_after as i64
+    }
+}
+
+/// Purges trashed strokes and compacts the slots of the store snapshot.
+pub fn compact(document: &mut RnotefileWrapper) -> anyhow::Result<CompactReport> {
+    let bytes_before = compressed_size(document)?;
+    let (removed_trashed, removed_vacant) = document.store_snapshot_mut().compact();
+    let bytes_after = compressed_size(document)?;
+
+    Ok(CompactReport {
+        removed_trashed,
+        removed_vacant,
+        bytes_before,
+        bytes_after,
+    })
+}
+
+/// The size of the serialized and compressed document
+fn compressed_size(document: &RnotefileWrapper) -> anyhow::Result<usize> {
+    Ok(crate::compress_to_gzip(&serde_json::to_vec(document)?, "")?.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rnotev0_4::Background;
+    use crate::rnotev0_5::tests::{line_stroke, roundtrip};
+    use crate::rnotev0_5::{DocumentBuilder, Format, Layout, ShapeBehaviour};
+
+    #[test]
+    fn compacted_document_can_be_loaded() {
+        let mut builder =
+            DocumentBuilder::new(Format::default(), Layout::Infinite, Background::default());
+        for i in 0..4 {
+            let offset = na::vector![0.0, f64::from(i) * 10.0];
+            builder.add_stroke(line_stroke(offset, offset + na::vector![100.0, 0.0]));
+        }
+        let mut document = builder.build();
+        let store = document.store_snapshot_mut();
+        store.remove_stroke(1);
+        store.set_trashed(3, true);
+
+        let report = compact(&mut document).unwrap();
+        let loaded = roundtrip(&document);
+        let entries = loaded
+            .strokes()
+            .map(|(key, stroke, trashed, _, chrono)| {
+                (key.index, stroke.bounds().center()[1], trashed, chrono)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!((report.removed_trashed, report.removed_vacant), (1, 1));
+        // the removed and the trashed stroke are gone, the others moved up behind the reserved first slot
+        assert_eq!(entries, vec![(1, 10.0, false, 1), (2, 30.0, false, 2)]);
+        assert_eq!(loaded.store_snapshot().stroke_components().len(), 3);
+    }
+
+    #[test]
+    fn compacting_moves_strokes_out_of_the_first_slot() {
+        // files written before the first slot was reserved have a stroke in it
+        let mut document =
+            DocumentBuilder::new(Format::default(), Layout::Infinite, Background::default())
+                .build();
+        let mut json = serde_json::to_value(&document).unwrap();
+        let stroke =
+            serde_json::to_value(line_stroke(na::vector![0.0, 0.0], na::vector![1.0, 1.0]))
+                .unwrap();
+        let store = &mut json["data"]["store_snapshot"];
+        store["stroke_components"][0] = serde_json::json!({ "value": stroke, "version": 1 });
+        store["chrono_components"][0] = serde_json::json!({ "value": { "t": 1 }, "version": 1 });
+        document = serde_json::from_value(json).unwrap();
+        assert!(document.assert_valid().is_err());
+
+        compact(&mut document).unwrap();
+        let loaded = roundtrip(&document);
+
+        assert_eq!(
+            loaded
+                .strokes()
+                .map(|(key, ..)| key.index)
+                .collect::<Vec<usize>>(),
+            vec![1]
+        );
+    }
+}
@@ -0,0 +1,240 @@
+use p2d::bounding_volume::{BoundingVolume, AABB};
+use serde::Serialize;
+
+use crate::rnotev0_5::penpath::{PenPath, Segment};
+use crate::rnotev0_5::stroke::Stroke;
+use crate::rnotev0_5::{RnotefileWrapper, ShapeBehaviour, TransformBehaviour};
+
+/// The number of samples per segment when searching the boundary crossings of curves
+const CROSSING_SAMPLES: usize = 32;
+/// The number of bisection steps to refine a boundary crossing
+const CROSSING_BISECTIONS: usize = 40;
+
+/// The result of cropping a document
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct CropReport {
+    /// The number of kept strokes
+    pub kept: usize,
+    /// The number of removed strokes
+    pub removed: usize,
+    /// The number of pen paths which were clipped at the boundary
+    pub clipped: usize,
+}
+
+/// Crops the document to the region.
+///
+/// Strokes which don't intersect the region are removed, leaving vacant slots. With `clip_paths` the pen paths of
+/// brush strokes are clipped at the boundary by splitting their segments, other strokes are kept whole.
+/// The remaining strokes are translated so that the region starts at the origin, and the document extents are fitted
+/// to the region.
+pub fn crop(document: &mut RnotefileWrapper, region: AABB, clip_paths: bool) -> CropReport {
+    let mut report = CropReport::default();
+    let store = document.store_snapshot_mut();
+
+    let indices = store
+        .strokes()
+        .map(|(key, _, _, _, _)| key.index)
+        .collect::<Vec<usize>>();
+    for index in indices {
+        let stroke = match store.stroke_mut(index) {
+            Some(stroke) => stroke,
+            None => continue,
+        };
+        if !region.intersects(&stroke.bounds()) {
+            store.remove_stroke(index);
+            report.removed += 1;
+            continue;
+        }
+
+        if let (true, Stroke::BrushStroke(brushstroke)) = (clip_paths, &mut *stroke) {
+            if !region.contains(&brushstroke.path.bounds()) {
+                match clip_penpath(&brushstroke.path, region) {
+                    Some(path) => {
+                        brushstroke.path = path;
+                        report.clipped += 1;
+                    }
+                    None => {
+                        // the bounds intersect, but the path itself lies outside
+                        store.remove_stroke(index);
+                        report.removed += 1;
+                        continue;
+                    }
+                }
+            }
+        }
+        report.kept += 1;
+    }
+
+    for stroke in store.strokes_mut() {
+        stroke.translate(-region.mins.coords);
+    }
+    document.document_mut().fit_extents(Some(AABB::new(
+        na::Point2::origin(),
+        region.extents().into(),
+    )));
+
+    report
+}
+
+/// Clips the pen path to the region, splitting segments which cross its boundary.
+/// Returns None if no part of the path lies inside the region.
+fn clip_penpath(path: &PenPath, region: AABB) -> Option<PenPath> {
+    let inside = |pos: na::Vector2<f64>| region.contains_local_point(&pos.into());
+
+    let segments = path
+        .iter()
+        .flat_map(|segment| {
+            let mut pieces = vec![];
+            let mut rest = segment.clone();
+            let mut t_prev = 0.0;
+
+            for t in boundary_crossings(segment, region) {
+                let (piece, remainder) = rest.split((t - t_prev) / (1.0 - t_prev));
+                pieces.push(piece);
+                rest = remainder;
+                t_prev = t;
+            }
+            pieces.push(rest);
+
+            // the pieces lie either completely inside or outside, so testing their middle is sufficient
+            pieces
+                .into_iter()
+                .filter(|piece| inside(piece.point_at(0.5)))
+                .collect::<Vec<Segment>>()
+        })
+        .collect::<Vec<Segment>>();
+
+    if segments.is_empty() {
+        None
+    } else {
+        Some(PenPath(segments.into()))
+    }
+}
+
+/// The sorted t values between 0.0 and 1.0 where the segment crosses the boundary of the region
+fn boundary_crossings(segment: &Segment, region: AABB) -> Vec<f64> {
+    let mut crossings = vec![];
+    if let Segment::Dot { .. } = segment {
+        return crossings;
+    }
+
+    for axis in 0..2 {
+        for boundary in [region.mins[axis], region.maxs[axis]] {
+            let distance = |t: f64| segment.point_at(t)[axis] - boundary;
+
+            for i in 0..CROSSING_SAMPLES {
+                let (mut t0, mut t1) = (
+                    i as f64 / CROSSING_SAMPLES as f64,
+                    (i + 1) as f64 / CROSSING_SAMPLES as f64,
+                );
+                if distance(t0).signum() == distance(t1).signum() {
+                    continue;
+                }
+                for _ in 0..CROSSING_BISECTIONS {
+                    let t_mid = (t0 + t1) * 0.5;
+                    if distance(t0).signum() == distance(t_mid).signum() {
+                        t0 = t_mid;
+                    } else {
+                        t1 = t_mid;
+                    }
+                }
+                crossings.push((t0 + t1) * 0.5);
+            }
+        }
+    }
+
+    crossings.retain(|t| *t > f64::EPSILON && *t < 1.0 - f64::EPSILON);
+    crossings.sort_by(f64::total_cmp);
+    crossings.dedup_by(|a, b| (*a - *b).abs() < 1e-9);
+    crossings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rnotev0_4::Background;
+    use crate::rnotev0_5::penpath::Element;
+    use crate::rnotev0_5::tests::{line_stroke, rectangle_stroke, roundtrip};
+    use crate::rnotev0_5::{DocumentBuilder, Format, Layout};
+
+    fn region() -> AABB {
+        AABB::new(na::point![100.0, 100.0], na::point![300.0, 200.0])
+    }
+
+    /// A document with a line crossing the left boundary of the region, a rectangle inside and a line outside
+    fn document() -> RnotefileWrapper {
+        let mut builder =
+            DocumentBuilder::new(Format::default(), Layout::Infinite, Background::default());
+        builder.add_stroke(line_stroke(
+            na::vector![50.0, 150.0],
+            na::vector![150.0, 150.0],
+        ));
+        builder.add_stroke(rectangle_stroke(
+            na::point![200.0, 120.0],
+            na::point![250.0, 180.0],
+        ));
+        builder.add_stroke(line_stroke(
+            na::vector![500.0, 500.0],
+            na::vector![600.0, 600.0],
+        ));
+        builder.build()
+    }
+
+    #[test]
+    fn cropped_document_can_be_loaded() {
+        let mut document = document();
+
+        let report = crop(&mut document, region(), false);
+        let loaded = roundtrip(&document);
+        let bounds = loaded
+            .strokes()
+            .map(|(_, stroke, _, _, _)| stroke.bounds())
+            .collect::<Vec<AABB>>();
+
+        assert_eq!((report.kept, report.removed, report.clipped), (2, 1, 0));
+        assert_eq!(bounds.len(), 2);
+        // the region is moved to the origin
+        assert!((bounds[1].center() - na::point![125.0, 50.0]).norm() < 1e-9);
+        assert_eq!(loaded.document().bounds().mins, na::point![0.0, 0.0]);
+        assert!(loaded.document().width >= 200.0 && loaded.document().height >= 100.0);
+    }
+
+    #[test]
+    fn paths_are_clipped_at_the_boundary() {
+        let mut document = document();
+
+        let report = crop(&mut document, region(), true);
+        let loaded = roundtrip(&document);
+        let path = match loaded.strokes().next() {
+            Some((_, Stroke::BrushStroke(brushstroke), _, _, _)) => brushstroke.path.clone(),
+            _ => panic!("expected the clipped brush stroke"),
+        };
+
+        assert_eq!(report.clipped, 1);
+        assert_eq!(path.len(), 1);
+        assert!((path[0].start().pos - na::vector![0.0, 50.0]).norm() < 1e-6);
+        assert!((path[0].end().pos - na::vector![50.0, 50.0]).norm() < 1e-9);
+    }
+
+    #[test]
+    fn curves_are_split_at_the_crossings() {
+        let segment = Segment::CubBez {
+            start: Element::new(na::vector![0.0, 0.0], 0.5),
+            cp1: na::vector![100.0, 300.0],
+            cp2: na::vector![300.0, -100.0],
+            end: Element::new(na::vector![400.0, 200.0], 0.5),
+        };
+        let region = AABB::new(na::point![-10.0, -10.0], na::point![500.0, 100.0]);
+
+        let clipped = clip_penpath(&PenPath::new_w_segment(segment), region).unwrap();
+
+        // the curve leaves the region through the bottom, comes back and leaves it again
+        assert_eq!(clipped.len(), 2);
+        for piece in clipped.iter() {
+            for t in [0.0, 0.25, 0.5, 0.75, 1.0] {
+                let point = piece.point_at(t);
+                assert!(point[1] <= 100.0 + 1e-6, "{point:?} lies outside");
+            }
+        }
+    }
+}
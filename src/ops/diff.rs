@@ -0,0 +1,602 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::rnotev0_5::penpath::Segment;
+use crate::rnotev0_5::shapes::{Ellipse, Rectangle, Shape};
+use crate::rnotev0_5::store::StoreSnapshot;
+use crate::rnotev0_5::stroke::Stroke;
+use crate::rnotev0_5::{Document, RnotefileWrapper};
+
+/// The maximum mean distance (in document coordinates) between the geometry of two strokes, after aligning their
+/// centroids, for them to be considered the same stroke when they could not be matched by their slot.
+pub const SIMILARITY_TOLERANCE: f64 = 1.0;
+/// The maximum distance (in document coordinates) between the centroids of two strokes for them to be considered
+/// the same stroke when they could not be matched by their slot.
+pub const MOVE_TOLERANCE: f64 = 100.0;
+
+/// The kind of a stroke change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// The stroke only exists in the second document
+    Added,
+    /// The stroke only exists in the first document
+    Removed,
+    /// The geometry of the stroke differs (translated, rotated, resized)
+    Moved,
+    /// The style of the stroke differs
+    Restyled,
+    /// The data of the image differs
+    ContentChanged,
+}
+
+/// A single change of a stroke between two documents
+#[derive(Debug, Clone, Serialize)]
+pub struct StrokeChange {
+    pub kind: ChangeKind,
+    /// The slot index of the stroke in the first document
+    pub index_a: Option<usize>,
+    /// The slot index of the stroke in the second document
+    pub index_b: Option<usize>,
+    /// The stroke type
+    pub stroke_type: &'static str,
+}
+
+/// A changed field of the document
+#[derive(Debug, Clone, Serialize)]
+pub struct DocumentChange {
+    pub field: &'static str,
+    pub a: serde_json::Value,
+    pub b: serde_json::Value,
+}
+
+/// The structural diff between two documents.
+///
+/// Strokes are matched by their slot index and slot version. Strokes which could not be matched by their slot
+/// (e.g. the slot was vacated and reused) are matched by the similarity of their geometry and their distance
+/// as a fallback, assigning the closest pairs first. Trashed strokes are treated as if they were removed.
+#[derive(Debug, Clone, Serialize)]
+pub struct DocumentDiff {
+    pub document_changes: Vec<DocumentChange>,
+    pub stroke_changes: Vec<StrokeChange>,
+}
+
+impl DocumentDiff {
+    pub fn new(a: &RnotefileWrapper, b: &RnotefileWrapper) -> Self {
+        let strokes_a = live_strokes(a.store_snapshot());
+        let strokes_b = live_strokes(b.store_snapshot());
+
+        let mut stroke_changes = vec![];
+        let mut unmatched_a = vec![];
+        let mut unmatched_b = vec![];
+
+        for index in 0..strokes_a.len().max(strokes_b.len()) {
+            match (
+                strokes_a.get(index).copied().flatten(),
+                strokes_b.get(index).copied().flatten(),
+            ) {
+                (Some((version_a, stroke_a)), Some((version_b, stroke_b)))
+                    if version_a == version_b =>
+                {
+                    compare_strokes((index, stroke_a), (index, stroke_b), &mut stroke_changes);
+                }
+                (a, b) => {
+                    if let Some((_, stroke)) = a {
+                        unmatched_a.push((index, stroke));
+                    }
+                    if let Some((_, stroke)) = b {
+                        unmatched_b.push((index, stroke));
+                    }
+                }
+            }
+        }
+
+        let matches = match_similar(&unmatched_a, &unmatched_b);
+        let mut matched_b = vec![false; unmatched_b.len()];
+        matches.iter().flatten().for_each(|&j| matched_b[j] = true);
+        for (i, &(index_a, stroke_a)) in unmatched_a.iter().enumerate() {
+            match matches[i] {
+                Some(j) => {
+                    compare_strokes((index_a, stroke_a), unmatched_b[j], &mut stroke_changes)
+                }
+                None => stroke_changes.push(StrokeChange {
+                    kind: ChangeKind::Removed,
+                    index_a: Some(index_a),
+                    index_b: None,
+                    stroke_type: stroke_a.type_name(),
+                }),
+            }
+        }
+
+        stroke_changes.extend(
+            unmatched_b
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| !matched_b[*j])
+                .map(|(_, &(index_b, stroke_b))| StrokeChange {
+                    kind: ChangeKind::Added,
+                    index_a: None,
+                    index_b: Some(index_b),
+                    stroke_type: stroke_b.type_name(),
+                }),
+        );
+
+        Self {
+            document_changes: document_changes(a.document(), b.document()),
+            stroke_changes,
+        }
+    }
+
+    /// Returns true if the documents are structurally equal
+    pub fn is_empty(&self) -> bool {
+        self.document_changes.is_empty() && self.stroke_changes.is_empty()
+    }
+
+    /// The number of stroke changes of the given kind
+    pub fn count(&self, kind: ChangeKind) -> usize {
+        self.stroke_changes
+            .iter()
+            .filter(|change| change.kind == kind)
+            .count()
+    }
+}
+
+impl fmt::Display for DocumentDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in self.document_changes.iter() {
+            writeln!(f, "document: {} {} -> {}", change.field, change.a, change.b)?;
+        }
+
+        for change in self.stroke_changes.iter() {
+            let slots = match (change.index_a, change.index_b) {
+                (Some(a), Some(b)) if a == b => format!("slot {a}"),
+                (Some(a), Some(b)) => format!("slot {a} -> {b}"),
+                (Some(a), None) => format!("slot {a}"),
+                (None, Some(b)) => format!("slot {b}"),
+                (None, None) => unreachable!(),
+            };
+            match change.kind {
+                ChangeKind::Added => writeln!(f, "+ {} ({slots})", change.stroke_type)?,
+                ChangeKind::Removed => writeln!(f, "- {} ({slots})", change.stroke_type)?,
+                ChangeKind::Moved => writeln!(f, "~ {} moved ({slots})", change.stroke_type)?,
+                ChangeKind::Restyled => writeln!(f, "~ {} restyled ({slots})", change.stroke_type)?,
+                ChangeKind::ContentChanged => {
+                    writeln!(f, "~ {} content changed ({slots})", change.stroke_type)?
+                }
+            }
+        }
+
+        writeln!(
+            f,
+            "{} added, {} removed, {} moved, {} restyled, {} content changed, {} document changes",
+            self.count(ChangeKind::Added),
+            self.count(ChangeKind::Removed),
+            self.count(ChangeKind::Moved),
+            self.count(ChangeKind::Restyled),
+            self.count(ChangeKind::ContentChanged),
+            self.document_changes.len()
+        )
+    }
+}
+
+/// Collects the strokes which are not trashed, together with their slot version, indexed by their slot
+fn live_strokes(store: &StoreSnapshot) -> Vec<Option<(u32, &Stroke)>> {
+    store
+        .stroke_components()
+        .iter()
+        .enumerate()
+        .map(|(index, slot)| {
+            slot.value
+                .as_ref()
+                .filter(|_| !store.is_trashed(index))
+                .map(|stroke| (slot.version, stroke))
+        })
+        .collect()
+}
+
+fn compare_strokes(
+    (index_a, stroke_a): (usize, &Stroke),
+    (index_b, stroke_b): (usize, &Stroke),
+    changes: &mut Vec<StrokeChange>,
+) {
+    let change = |kind| StrokeChange {
+        kind,
+        index_a: Some(index_a),
+        index_b: Some(index_b),
+        stroke_type: stroke_b.type_name(),
+    };
+
+    if stroke_a.type_name() != stroke_b.type_name() {
+        changes.push(StrokeChange {
+            index_b: None,
+            stroke_type: stroke_a.type_name(),
+            ..change(ChangeKind::Removed)
+        });
+        changes.push(StrokeChange {
+            index_a: None,
+            ..change(ChangeKind::Added)
+        });
+        return;
+    }
+    if geometry_points(stroke_a) != geometry_points(stroke_b) {
+        changes.push(change(ChangeKind::Moved));
+    }
+    if stroke_a.style() != stroke_b.style() {
+        changes.push(change(ChangeKind::Restyled));
+    }
+    if content_differs(stroke_a, stroke_b) {
+        changes.push(change(ChangeKind::ContentChanged));
+    }
+}
+
+/// Returns true if the image data of the strokes differs. Other strokes have no content besides their geometry
+/// and style.
+fn content_differs(a: &Stroke, b: &Stroke) -> bool {
+    match (a, b) {
+        (Stroke::BitmapImage(a), Stroke::BitmapImage(b)) => {
+            (
+                a.image.pixel_width,
+                a.image.pixel_height,
+                a.image.memory_format,
+            ) != (
+                b.image.pixel_width,
+                b.image.pixel_height,
+                b.image.memory_format,
+            ) || a.image.data != b.image.data
+        }
+        (Stroke::VectorImage(a), Stroke::VectorImage(b)) => a.svg_data != b.svg_data,
+        _ => false,
+    }
+}
+
+/// Matches the strokes by their similarity, assigning the pairs with the smallest distance first.
+/// Returns the index of the matched stroke of `b` for every stroke of `a`.
+fn match_similar(a: &[(usize, &Stroke)], b: &[(usize, &Stroke)]) -> Vec<Option<usize>> {
+    let mut candidates = a
+        .iter()
+        .enumerate()
+        .flat_map(|(i, (_, stroke_a))| {
+            b.iter().enumerate().filter_map(move |(j, (_, stroke_b))| {
+                similarity_distance(stroke_a, stroke_b).map(|dist| (dist, i, j))
+            })
+        })
+        .collect::<Vec<(f64, usize, usize)>>();
+    // the stable sort keeps the slot order for equal distances
+    candidates.sort_by(|(first, _, _), (second, _, _)| first.total_cmp(second));
+
+    let mut matches = vec![None; a.len()];
+    let mut matched_b = vec![false; b.len()];
+    for (_, i, j) in candidates {
+        if matches[i].is_none() && !matched_b[j] {
+            matches[i] = Some(j);
+            matched_b[j] = true;
+        }
+    }
+
+    matches
+}
+
+/// The distance between the strokes: the distance between the centroids of their geometry, plus the mean distance
+/// between their geometry after aligning the centroids. Returns None if the strokes are not similar,
+/// see `SIMILARITY_TOLERANCE` and `MOVE_TOLERANCE`.
+fn similarity_distance(a: &Stroke, b: &Stroke) -> Option<f64> {
+    if a.type_name() != b.type_name() {
+        return None;
+    }
+    let points_a = geometry_points(a);
+    let points_b = geometry_points(b);
+    if points_a.is_empty() || points_a.len() != points_b.len() {
+        return None;
+    }
+    let offset = centroid(&points_b) - centroid(&points_a);
+    if offset.magnitude() > MOVE_TOLERANCE {
+        return None;
+    }
+
+    let dist = points_a
+        .iter()
+        .zip(points_b.iter())
+        .map(|(a, b)| (b - (a + offset)).magnitude())
+        .sum::<f64>()
+        / points_a.len() as f64;
+
+    (dist <= SIMILARITY_TOLERANCE).then_some(offset.magnitude() + dist)
+}
+
+fn centroid(points: &[na::Point2<f64>]) -> na::Point2<f64> {
+    let sum = points
+        .iter()
+        .fold(na::Vector2::zeros(), |sum, point| sum + point.coords);
+
+    na::Point2::from(sum / points.len() as f64)
+}
+
+/// The points which define the geometry of the stroke in document coordinates
+fn geometry_points(stroke: &Stroke) -> Vec<na::Point2<f64>> {
+    match stroke {
+        Stroke::BrushStroke(brushstroke) => {
+            brushstroke.path.iter().flat_map(segment_points).collect()
+        }
+        Stroke::ShapeStroke(shapestroke) => match &shapestroke.shape {
+            Shape::Line(line) => vec![line.start.into(), line.end.into()],
+            Shape::Rectangle(rectangle) => rectangle_points(rectangle),
+            Shape::Ellipse(ellipse) => ellipse_points(ellipse),
+            Shape::QuadraticBezier(quadbez) => {
+                vec![quadbez.start.into(), quadbez.cp.into(), quadbez.end.into()]
+            }
+            Shape::CubicBezier(cubbez) => vec![
+                cubbez.start.into(),
+                cubbez.cp1.into(),
+                cubbez.cp2.into(),
+                cubbez.end.into(),
+            ],
+            Shape::Segment(segment) => segment_points(segment),
+        },
+        Stroke::VectorImage(vectorimage) => rectangle_points(&vectorimage.rectangle),
+        Stroke::BitmapImage(bitmapimage) => rectangle_points(&bitmapimage.rectangle),
+    }
+}
+
+fn segment_points(segment: &Segment) -> Vec<na::Point2<f64>> {
+    match segment {
+        Segment::Dot { element } => vec![element.pos.into()],
+        Segment::Line { start, end } => vec![start.pos.into(), end.pos.into()],
+        Segment::QuadBez { start, cp, end } => {
+            vec![start.pos.into(), (*cp).into(), end.pos.into()]
+        }
+        Segment::CubBez {
+            start,
+            cp1,
+            cp2,
+            end,
+        } => vec![
+            start.pos.into(),
+            (*cp1).into(),
+            (*cp2).into(),
+            end.pos.into(),
+        ],
+    }
+}
+
+fn rectangle_points(rectangle: &Rectangle) -> Vec<na::Point2<f64>> {
+    rectangle
+        .outline_lines()
+        .iter()
+        .map(|line| line.start.into())
+        .collect()
+}
+
+fn ellipse_points(ellipse: &Ellipse) -> Vec<na::Point2<f64>> {
+    let radii = ellipse.radii;
+
+    [
+        na::point![0.0, 0.0],
+        na::point![radii[0], 0.0],
+        na::point![-radii[0], 0.0],
+        na::point![0.0, radii[1]],
+        na::point![0.0, -radii[1]],
+    ]
+    .into_iter()
+    .map(|point| ellipse.transform.transform_point(point))
+    .collect()
+}
+
+fn document_changes(a: &Document, b: &Document) -> Vec<DocumentChange> {
+    fn change<T: Serialize + PartialEq>(
+        field: &'static str,
+        a: &T,
+        b: &T,
+        changes: &mut Vec<DocumentChange>,
+    ) {
+        if a != b {
+            changes.push(DocumentChange {
+                field,
+                a: serde_json::to_value(a).unwrap_or_default(),
+                b: serde_json::to_value(b).unwrap_or_default(),
+            });
+        }
+    }
+
+    let mut changes = vec![];
+    change("x", &a.x, &b.x, &mut changes);
+    change("y", &a.y, &b.y, &mut changes);
+    change("width", &a.width, &b.width, &mut changes);
+    change("height", &a.height, &b.height, &mut changes);
+    change("format", &a.format, &b.format, &mut changes);
+    change("background", &a.background, &b.background, &mut changes);
+    change("layout", &a.layout(), &b.layout(), &mut changes);
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rnotev0_4::Background;
+    use crate::rnotev0_5::penpath::Element;
+    use crate::rnotev0_5::stroke::{BitmapImage, BrushStroke, Style, VectorImage};
+    use crate::rnotev0_5::tests::{line_stroke, rectangle_stroke};
+    use crate::rnotev0_5::{DocumentBuilder, Format, Layout, TransformBehaviour};
+
+    fn dot(pos: na::Vector2<f64>) -> Stroke {
+        Stroke::BrushStroke(BrushStroke::new(
+            Segment::Dot {
+                element: Element::new(pos, 0.5),
+            },
+            Style::default(),
+        ))
+    }
+
+    fn document(strokes: Vec<Stroke>) -> RnotefileWrapper {
+        let mut builder =
+            DocumentBuilder::new(Format::default(), Layout::Infinite, Background::default());
+        for stroke in strokes {
+            builder.add_stroke(stroke);
+        }
+        builder.build()
+    }
+
+    /// Moves the stroke into a new slot, as if it was removed and inserted again
+    fn reinsert(document: &mut RnotefileWrapper, index: usize, offset: na::Vector2<f64>) -> usize {
+        let store = document.store_snapshot_mut();
+        let mut stroke = store.remove_stroke(index).unwrap();
+        stroke.translate(offset);
+        store.insert_stroke(stroke)
+    }
+
+    fn changes(diff: &DocumentDiff) -> Vec<(ChangeKind, Option<usize>, Option<usize>)> {
+        diff.stroke_changes
+            .iter()
+            .map(|change| (change.kind, change.index_a, change.index_b))
+            .collect()
+    }
+
+    #[test]
+    fn equal_documents_have_no_changes() {
+        let a = document(vec![
+            line_stroke(na::vector![0.0, 0.0], na::vector![10.0, 10.0]),
+            rectangle_stroke(na::point![20.0, 20.0], na::point![40.0, 30.0]),
+        ]);
+
+        assert!(DocumentDiff::new(&a, &a.clone()).is_empty());
+    }
+
+    #[test]
+    fn strokes_matched_by_slot_are_moved_and_restyled() {
+        let a = document(vec![line_stroke(
+            na::vector![0.0, 0.0],
+            na::vector![10.0, 10.0],
+        )]);
+        let mut b = a.clone();
+        let stroke = b.store_snapshot_mut().stroke_mut(1).unwrap();
+        stroke.translate(na::vector![500.0, 0.0]);
+        stroke.style_mut().unwrap().set_stroke_width(10.0);
+
+        let diff = DocumentDiff::new(&a, &b);
+
+        assert_eq!(
+            changes(&diff),
+            vec![
+                (ChangeKind::Moved, Some(1), Some(1)),
+                (ChangeKind::Restyled, Some(1), Some(1))
+            ]
+        );
+    }
+
+    #[test]
+    fn reinserted_strokes_nearby_are_matched() {
+        let a = document(vec![line_stroke(
+            na::vector![0.0, 0.0],
+            na::vector![10.0, 10.0],
+        )]);
+        let mut b = a.clone();
+        let index_b = reinsert(&mut b, 1, na::vector![5.0, 0.0]);
+
+        let diff = DocumentDiff::new(&a, &b);
+
+        assert_eq!(
+            changes(&diff),
+            vec![(ChangeKind::Moved, Some(1), Some(index_b))]
+        );
+    }
+
+    #[test]
+    fn distant_strokes_of_the_same_shape_are_not_matched() {
+        let a = document(vec![dot(na::vector![0.0, 0.0])]);
+        let mut b = a.clone();
+        let index_b = reinsert(&mut b, 1, na::vector![500.0, 500.0]);
+
+        let diff = DocumentDiff::new(&a, &b);
+
+        assert_eq!(
+            changes(&diff),
+            vec![
+                (ChangeKind::Removed, Some(1), None),
+                (ChangeKind::Added, None, Some(index_b))
+            ]
+        );
+    }
+
+    #[test]
+    fn closest_strokes_are_matched_first() {
+        let a = document(vec![
+            dot(na::vector![0.0, 0.0]),
+            dot(na::vector![30.0, 0.0]),
+        ]);
+        let mut b = a.clone();
+        // the second dot moves close to where the first one was, the first one moves away
+        let index_second = reinsert(&mut b, 2, na::vector![-2.0, 0.0]);
+        let index_first = reinsert(&mut b, 1, na::vector![-50.0, 0.0]);
+
+        let diff = DocumentDiff::new(&a, &b);
+
+        assert_eq!(
+            changes(&diff),
+            vec![
+                (ChangeKind::Moved, Some(1), Some(index_first)),
+                (ChangeKind::Moved, Some(2), Some(index_second))
+            ]
+        );
+    }
+
+    #[test]
+    fn replaced_image_data_is_a_change() {
+        let png = |pixel: [u8; 4]| {
+            let mut bytes = vec![];
+            image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(2, 2, image::Rgba(pixel)))
+                .write_to(&mut bytes, image::ImageOutputFormat::Png)
+                .unwrap();
+            bytes
+        };
+        let svg = |color: &str| {
+            format!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10"><rect fill="{color}" width="10" height="10"/></svg>"#
+            )
+        };
+        let bitmapimage = |pixel| {
+            BitmapImage::import_from_image_bytes(
+                &png(pixel),
+                image::ImageFormat::Png,
+                na::vector![0.0, 0.0],
+                Some(20.0),
+            )
+            .unwrap()
+        };
+        let vectorimage = |color| {
+            VectorImage::import_from_svg_data(&svg(color), na::vector![50.0, 0.0], Some(20.0))
+                .unwrap()
+        };
+        let a = document(vec![
+            bitmapimage([255, 0, 0, 255]).into(),
+            vectorimage("red").into(),
+        ]);
+        let b = document(vec![
+            bitmapimage([0, 0, 255, 255]).into(),
+            vectorimage("blue").into(),
+        ]);
+
+        let diff = DocumentDiff::new(&a, &b);
+
+        assert_eq!(
+            changes(&diff),
+            vec![
+                (ChangeKind::ContentChanged, Some(1), Some(1)),
+                (ChangeKind::ContentChanged, Some(2), Some(2))
+            ]
+        );
+    }
+
+    #[test]
+    fn trashed_strokes_are_removed() {
+        let a = document(vec![line_stroke(
+            na::vector![0.0, 0.0],
+            na::vector![10.0, 10.0],
+        )]);
+        let mut b = a.clone();
+        b.store_snapshot_mut().set_trashed(1, true);
+
+        let diff = DocumentDiff::new(&a, &b);
+
+        assert_eq!(changes(&diff), vec![(ChangeKind::Removed, Some(1), None)]);
+    }
+}
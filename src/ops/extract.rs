@@ -0,0 +1,157 @@
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::rnotev0_5::shapes::Rectangle;
+use crate::rnotev0_5::stroke::Stroke;
+use crate::rnotev0_5::RnotefileWrapper;
+
+/// The file name of the manifest written next to the extracted images
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+/// An image extracted from a document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractedImage {
+    /// The slot index of the image
+    pub index: usize,
+    /// The stroke type, either `bitmapimage` or `vectorimage`
+    pub stroke_type: String,
+    /// The file name of the image, relative to the manifest
+    pub file: String,
+    /// The rectangle of the image, including its transform
+    pub rectangle: Rectangle,
+    /// The center of the image in the coordinate space of the doc
+    pub position: na::Vector2<f64>,
+    /// The size of the image in the coordinate space of the doc
+    pub size: na::Vector2<f64>,
+    /// The encoded image (png or svg)
+    #[serde(skip)]
+    pub bytes: Vec<u8>,
+}
+
+/// The manifest of the images extracted from a document
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImageManifest {
+    pub images: Vec<ExtractedImage>,
+}
+
+/// Extracts the bitmap images as png and the vector images as svg, named by their slot index.
+/// Trashed images are extracted as well.
+pub fn extract_images(document: &RnotefileWrapper) -> anyhow::Result<ImageManifest> {
+    let images = document
+        .store_snapshot()
+        .stroke_components()
+        .iter()
+        .enumerate()
+        .filter_map(|(index, slot)| {
+            let stroke = slot.value.as_ref()?;
+            let (rectangle, file, bytes) = match stroke {
+                Stroke::BitmapImage(bitmapimage) => (
+                    bitmapimage.rectangle,
+                    format!("image-{index}.png"),
+                    bitmapimage
+                        .image
+                        .clone()
+                        .into_encoded_bytes(image::ImageOutputFormat::Png)
+                        .with_context(|| format!("failed to encode the image in slot {index}")),
+                ),
+                Stroke::VectorImage(vectorimage) => (
+                    vectorimage.rectangle,
+                    format!("image-{index}.svg"),
+                    Ok(vectorimage.svg_data.clone().into_bytes()),
+                ),
+                _ => return None,
+            };
+
+            Some(bytes.map(|bytes| ExtractedImage {
+                index,
+                stroke_type: stroke.type_name().to_string(),
+                file,
+                rectangle,
+                position: rectangle.transform.translation_part(),
+                size: rectangle.size(),
+                bytes,
+            }))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(ImageManifest { images })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rnotev0_4::Background;
+    use crate::rnotev0_5::stroke::{BitmapImage, VectorImage};
+    use crate::rnotev0_5::tests::line_stroke;
+    use crate::rnotev0_5::{DocumentBuilder, Format, Layout};
+
+    const SVG: &str = r#"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="10"></svg>"#;
+
+    fn document() -> RnotefileWrapper {
+        let mut png = vec![];
+        image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+            3,
+            2,
+            image::Rgba([255, 0, 0, 128]),
+        ))
+        .write_to(&mut png, image::ImageOutputFormat::Png)
+        .unwrap();
+
+        let mut builder =
+            DocumentBuilder::new(Format::default(), Layout::Infinite, Background::default());
+        builder.add_stroke(line_stroke(na::vector![0.0, 0.0], na::vector![10.0, 10.0]));
+        builder.add_image(
+            BitmapImage::import_from_image_bytes(
+                &png,
+                image::ImageFormat::Png,
+                na::vector![10.0, 20.0],
+                Some(30.0),
+            )
+            .unwrap(),
+        );
+        builder.add_image(
+            VectorImage::import_from_svg_data(SVG, na::vector![100.0, 0.0], None).unwrap(),
+        );
+        builder.build()
+    }
+
+    #[test]
+    fn images_are_extracted_with_their_placement() {
+        let manifest = extract_images(&document()).unwrap();
+
+        let files = manifest
+            .images
+            .iter()
+            .map(|image| (image.index, image.stroke_type.as_str(), image.file.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            files,
+            vec![
+                (2, "bitmapimage", "image-2.png"),
+                (3, "vectorimage", "image-3.svg")
+            ]
+        );
+
+        let bitmap = &manifest.images[0];
+        assert_eq!(bitmap.position, na::vector![25.0, 30.0]);
+        assert_eq!(bitmap.size, na::vector![30.0, 20.0]);
+        let decoded = image::load_from_memory(&bitmap.bytes).unwrap().into_rgba8();
+        assert_eq!(decoded.dimensions(), (3, 2));
+        assert_eq!(decoded.get_pixel(0, 0).0, [255, 0, 0, 128]);
+
+        assert_eq!(manifest.images[1].bytes, SVG.as_bytes());
+        assert_eq!(manifest.images[1].size, na::vector![20.0, 10.0]);
+    }
+
+    #[test]
+    fn manifest_roundtrips_without_the_image_data() {
+        let manifest = extract_images(&document()).unwrap();
+
+        let json = serde_json::to_string(&manifest).unwrap();
+        let loaded: ImageManifest = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(loaded.images.len(), 2);
+        assert!(loaded.images.iter().all(|image| image.bytes.is_empty()));
+        assert_eq!(loaded.images[0].position, manifest.images[0].position);
+    }
+}
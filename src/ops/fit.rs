@@ -0,0 +1,173 @@
+use p2d::bounding_volume::AABB;
+use serde::Serialize;
+
+use crate::rnotev0_5::{Document, Format, Layout, RnotefileWrapper, ShapeBehaviour};
+
+/// The distance strokes may extend beyond a page boundary and still be considered to be on the page
+pub const PAGE_BOUNDARY_TOLERANCE: f64 = Document::PAGE_BOUNDARY_TOLERANCE;
+
+/// The result of fitting the document extents
+#[derive(Debug, Clone, Serialize)]
+pub struct FitReport {
+    /// The document bounds before fitting
+    pub bounds_before: AABB,
+    /// The document bounds after fitting
+    pub bounds_after: AABB,
+    pub layout_before: Layout,
+    pub layout_after: Layout,
+    /// The offset the strokes were moved by to place them on the pages
+    pub offset: na::Vector2<f64>,
+}
+
+/// Recomputes the document extents from the bounds of the strokes which are not trashed.
+///
+/// When no layout is given, it is chosen heuristically: content which lies within the width of a page is considered
+/// page based, with the `FixedSize` layout if no stroke straddles the boundary between two pages and the
+/// `ContinuousVertical` layout otherwise. Other content keeps the `Infinite` layout.
+/// When a page based layout is given for content at negative coordinates, the strokes are moved onto the pages.
+pub fn fit_extents(document: &mut RnotefileWrapper, layout: Option<Layout>) -> FitReport {
+    let content = document.store_snapshot().strokes_bounds();
+    let layout_before = document.document().layout();
+    let layout_after = layout.unwrap_or_else(|| guess_layout(document, content));
+
+    let bounds_before = document.document().bounds();
+    document.document_mut().set_layout(layout_after);
+    let offset = document.fit_extents();
+
+    FitReport {
+        bounds_before,
+        bounds_after: document.document().bounds(),
+        layout_before,
+        layout_after,
+        offset,
+    }
+}
+
+/// Guesses the layout from the placement of the strokes
+pub fn guess_layout(document: &RnotefileWrapper, content: Option<AABB>) -> Layout {
+    let format = document.document().format;
+    let content = match content {
+        Some(content) => content,
+        None => return Layout::FixedSize,
+    };
+
+    if content.mins[0] < -PAGE_BOUNDARY_TOLERANCE
+        || content.mins[1] < -PAGE_BOUNDARY_TOLERANCE
+        || content.maxs[0] > format.width + PAGE_BOUNDARY_TOLERANCE
+    {
+        return Layout::Infinite;
+    }
+
+    let straddling = document
+        .store_snapshot()
+        .strokes()
+        .filter(|(_, _, trashed, _, _)| !trashed)
+        .any(|(_, stroke, _, _, _)| straddles_page_boundary(&format, stroke.bounds()));
+
+    if straddling {
+        Layout::ContinuousVertical
+    } else {
+        Layout::FixedSize
+    }
+}
+
+/// Returns true if the bounds cross a horizontal page boundary by more than the tolerance
+fn straddles_page_boundary(format: &Format, bounds: AABB) -> bool {
+    let first_page = ((bounds.mins[1] + PAGE_BOUNDARY_TOLERANCE) / format.height).floor();
+    let last_page = ((bounds.maxs[1] - PAGE_BOUNDARY_TOLERANCE) / format.height).floor();
+
+    last_page > first_page
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rnotev0_4::Background;
+    use crate::rnotev0_5::tests::{line_stroke, roundtrip};
+    use crate::rnotev0_5::DocumentBuilder;
+    use p2d::bounding_volume::BoundingVolume;
+
+    fn document(lines: &[(na::Vector2<f64>, na::Vector2<f64>)]) -> RnotefileWrapper {
+        let mut builder =
+            DocumentBuilder::new(Format::default(), Layout::Infinite, Background::default());
+        for &(start, end) in lines {
+            builder.add_stroke(line_stroke(start, end));
+        }
+        builder.build()
+    }
+
+    #[test]
+    fn layout_is_guessed_from_the_placement() {
+        let format = Format::default();
+
+        let mut on_page = document(&[(na::vector![10.0, 10.0], na::vector![100.0, 100.0])]);
+        let report = fit_extents(&mut on_page, None);
+        assert_eq!(report.layout_after, Layout::FixedSize);
+        assert_eq!(
+            report.bounds_after.maxs,
+            na::point![format.width, format.height]
+        );
+
+        let mut straddling = document(&[(
+            na::vector![10.0, format.height - 50.0],
+            na::vector![10.0, format.height + 50.0],
+        )]);
+        let report = fit_extents(&mut straddling, None);
+        assert_eq!(report.layout_after, Layout::ContinuousVertical);
+        assert_eq!(
+            report.bounds_after.maxs,
+            na::point![format.width, format.height * 2.0]
+        );
+
+        let mut wide = document(&[(
+            na::vector![10.0, 10.0],
+            na::vector![format.width * 3.0, 10.0],
+        )]);
+        let report = fit_extents(&mut wide, None);
+        assert_eq!(report.layout_after, Layout::Infinite);
+        assert_eq!(report.offset, na::Vector2::zeros());
+    }
+
+    #[test]
+    fn infinite_layout_covers_negative_content() {
+        let mut document = document(&[(na::vector![-100.0, -200.0], na::vector![10.0, 10.0])]);
+
+        let report = fit_extents(&mut document, Some(Layout::Infinite));
+
+        assert_eq!(report.offset, na::Vector2::zeros());
+        assert!(report
+            .bounds_after
+            .contains(&document.store_snapshot().strokes_bounds().unwrap()));
+    }
+
+    #[test]
+    fn negative_content_is_moved_onto_the_pages() {
+        let format = Format::default();
+
+        let mut fixed = document(&[(na::vector![-100.0, -200.0], na::vector![10.0, 10.0])]);
+        let report = fit_extents(&mut fixed, Some(Layout::FixedSize));
+        // by whole pages along both axes
+        assert_eq!(report.offset, na::vector![format.width, format.height]);
+        let content = fixed.store_snapshot().strokes_bounds().unwrap();
+        assert!(content.mins[0] >= 0.0 && content.mins[1] >= 0.0);
+        assert!(report.bounds_after.contains(&content));
+        roundtrip(&fixed);
+
+        let mut continuous = document(&[(na::vector![-100.0, -200.0], na::vector![10.0, 10.0])]);
+        let report = fit_extents(&mut continuous, Some(Layout::ContinuousVertical));
+        // the continuous layout has no page boundaries horizontally
+        assert_eq!(report.offset, na::vector![100.25, format.height]);
+        assert!(report
+            .bounds_after
+            .contains(&continuous.store_snapshot().strokes_bounds().unwrap()));
+    }
+
+    #[test]
+    fn content_within_the_tolerance_is_not_moved() {
+        let mut document = document(&[(na::vector![0.0, 0.0], na::vector![10.0, 10.0])]);
+
+        let report = fit_extents(&mut document, Some(Layout::FixedSize));
+
+        assert_eq!(report.offset, na::Vector2::zeros());
+    }
+}
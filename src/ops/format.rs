@@ -0,0 +1,243 @@
+use anyhow::anyhow;
+use clap::ArgEnum;
+use serde::Serialize;
+
+use crate::rnotev0_5::{Color, Format, Layout, Orientation, RnotefileWrapper, TransformBehaviour};
+
+/// Page size presets
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum PagePreset {
+    /// 210 x 297 mm
+    A4,
+    /// 148 x 210 mm
+    A5,
+    /// 8.5 x 11 in
+    Letter,
+}
+
+impl PagePreset {
+    /// The size of the page in portrait orientation in inches
+    pub fn size_inches(self) -> na::Vector2<f64> {
+        match self {
+            Self::A4 => na::vector![210.0, 297.0] / 25.4,
+            Self::A5 => na::vector![148.0, 210.0] / 25.4,
+            Self::Letter => na::vector![8.5, 11.0],
+        }
+    }
+
+    /// The size of the page in portrait orientation in pixels at the dpi
+    pub fn size(self, dpi: f64) -> na::Vector2<f64> {
+        (self.size_inches() * dpi).map(f64::round)
+    }
+}
+
+/// Overrides of the layout and format of a document. Unset fields are kept.
+#[derive(Debug, Clone, Default)]
+pub struct FormatOverrides {
+    pub layout: Option<Layout>,
+    /// The page size
+    pub preset: Option<PagePreset>,
+    /// The dpi. When it differs from the current dpi, the strokes are rescaled to keep their physical size.
+    pub dpi: Option<f64>,
+    pub orientation: Option<Orientation>,
+    pub border_color: Option<Color>,
+    pub show_borders: Option<bool>,
+}
+
+impl FormatOverrides {
+    /// Returns true if nothing is overridden
+    pub fn is_empty(&self) -> bool {
+        self.layout.is_none()
+            && self.preset.is_none()
+            && self.dpi.is_none()
+            && self.orientation.is_none()
+            && self.border_color.is_none()
+            && self.show_borders.is_none()
+    }
+}
+
+/// Applies the overrides to the document. Returns the factor the strokes were rescaled by.
+///
+/// The document extents are fitted to the strokes when the layout or the page size changes.
+pub fn apply_format(
+    document: &mut RnotefileWrapper,
+    overrides: &FormatOverrides,
+) -> anyhow::Result<f64> {
+    let old_format = document.document().format;
+    let old_layout = document.document().layout();
+    let format = overridden_format(&old_format, overrides);
+    validate_format(&format)?;
+
+    let scale = format.dpi / old_format.dpi;
+    if scale != 1.0 {
+        for stroke in document.store_snapshot_mut().strokes_mut() {
+            stroke.scale(na::vector![scale, scale]);
+            if let Some(style) = stroke.style_mut() {
+                style.set_stroke_width(style.stroke_width() * scale);
+            }
+        }
+
+        let doc = document.document_mut();
+        doc.x *= scale;
+        doc.y *= scale;
+        doc.width *= scale;
+        doc.height *= scale;
+    }
+
+    let doc = document.document_mut();
+    doc.format = format;
+    if let Some(layout) = overrides.layout {
+        doc.set_layout(layout);
+    }
+    if doc.layout() != old_layout
+        || format.width != old_format.width * scale
+        || format.height != old_format.height * scale
+    {
+        document.fit_extents();
+    }
+
+    Ok(scale)
+}
+
+fn overridden_format(format: &Format, overrides: &FormatOverrides) -> Format {
+    let mut new_format = *format;
+    new_format.dpi = overrides.dpi.unwrap_or(format.dpi);
+
+    match overrides.preset {
+        Some(preset) => {
+            let size = preset.size(new_format.dpi);
+            new_format.width = size[0];
+            new_format.height = size[1];
+        }
+        None => {
+            let scale = new_format.dpi / format.dpi;
+            new_format.width *= scale;
+            new_format.height *= scale;
+        }
+    }
+
+    if overrides.preset.is_some() || overrides.orientation.is_some() {
+        let orientation = overrides.orientation.unwrap_or(format.orientation);
+        let landscape = new_format.width > new_format.height;
+        if landscape != (orientation == Orientation::Landscape) {
+            std::mem::swap(&mut new_format.width, &mut new_format.height);
+        }
+        new_format.orientation = orientation;
+    }
+    if let Some(border_color) = overrides.border_color {
+        new_format.border_color = border_color;
+    }
+    if let Some(show_borders) = overrides.show_borders {
+        new_format.show_borders = show_borders;
+    }
+
+    new_format
+}
+
+/// Validates the format against the limits of `Format`
+pub fn validate_format(format: &Format) -> anyhow::Result<()> {
+    let check = |name: &str, value: f64, min: f64, max: f64| {
+        if (min..=max).contains(&value) {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "the format {name} {value} is outside of the valid range [{min}, {max}]"
+            ))
+        }
+    };
+
+    check("width", format.width, Format::WIDTH_MIN, Format::WIDTH_MAX)?;
+    check(
+        "height",
+        format.height,
+        Format::HEIGHT_MIN,
+        Format::HEIGHT_MAX,
+    )?;
+    check("dpi", format.dpi, Format::DPI_MIN, Format::DPI_MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rnotev0_4::Background;
+    use crate::rnotev0_5::tests::{line_stroke, roundtrip};
+    use crate::rnotev0_5::{DocumentBuilder, ShapeBehaviour};
+
+    fn document() -> RnotefileWrapper {
+        let mut builder =
+            DocumentBuilder::new(Format::default(), Layout::Infinite, Background::default());
+        builder.add_stroke(line_stroke(
+            na::vector![100.0, 100.0],
+            na::vector![200.0, 100.0],
+        ));
+        builder.build()
+    }
+
+    #[test]
+    fn preset_and_orientation_set_the_page_size() {
+        let mut document = document();
+        let overrides = FormatOverrides {
+            preset: Some(PagePreset::A4),
+            orientation: Some(Orientation::Landscape),
+            ..Default::default()
+        };
+
+        assert_eq!(apply_format(&mut document, &overrides).unwrap(), 1.0);
+
+        let format = document.document().format;
+        assert_eq!(format.width, 1123.0);
+        assert_eq!(format.height, 794.0);
+        assert_eq!(format.orientation, Orientation::Landscape);
+        roundtrip(&document);
+    }
+
+    #[test]
+    fn changed_dpi_rescales_the_strokes() {
+        let mut document = document();
+        let overrides = FormatOverrides {
+            dpi: Some(Format::DPI_DEFAULT * 2.0),
+            ..Default::default()
+        };
+
+        assert_eq!(apply_format(&mut document, &overrides).unwrap(), 2.0);
+
+        let format = document.document().format;
+        assert_eq!(format.width, Format::WIDTH_DEFAULT * 2.0);
+        assert_eq!(format.height, Format::HEIGHT_DEFAULT * 2.0);
+        let (_, stroke, ..) = document.strokes().next().unwrap();
+        assert_eq!(stroke.style().unwrap().stroke_width(), 2.0);
+        assert_eq!(stroke.bounds().center(), na::point![300.0, 200.0]);
+    }
+
+    #[test]
+    fn changed_layout_fits_the_extents() {
+        let mut document = document();
+        let overrides = FormatOverrides {
+            layout: Some(Layout::FixedSize),
+            show_borders: Some(false),
+            ..Default::default()
+        };
+
+        apply_format(&mut document, &overrides).unwrap();
+
+        let doc = document.document();
+        assert_eq!(doc.layout(), Layout::FixedSize);
+        assert!(!doc.format.show_borders);
+        assert_eq!(
+            doc.bounds().maxs,
+            na::point![Format::WIDTH_DEFAULT, Format::HEIGHT_DEFAULT]
+        );
+    }
+
+    #[test]
+    fn invalid_format_is_rejected() {
+        let mut document = document();
+        let overrides = FormatOverrides {
+            dpi: Some(Format::DPI_MAX * 2.0),
+            ..Default::default()
+        };
+
+        assert!(apply_format(&mut document, &overrides).is_err());
+        assert_eq!(document.document().format.dpi, Format::DPI_DEFAULT);
+    }
+}
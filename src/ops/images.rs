@@ -0,0 +1,298 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use anyhow::anyhow;
+use serde::Serialize;
+
+use crate::rnotev0_5::stroke::bitmapimage::Image;
+use crate::rnotev0_5::stroke::Stroke;
+use crate::rnotev0_5::RnotefileWrapper;
+
+/// The size analysis of a bitmap image in a document
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageAnalysis {
+    /// The slot index of the image
+    pub index: usize,
+    /// The width of the image data
+    pub pixel_width: u32,
+    /// The height of the image data
+    pub pixel_height: u32,
+    /// The width the image needs to be displayed at the dpi
+    pub needed_width: u32,
+    /// The height the image needs to be displayed at the dpi
+    pub needed_height: u32,
+    /// The size of the raw image data
+    pub data_bytes: usize,
+    /// The size of the base64 encoded image data stored in the file
+    pub base64_bytes: usize,
+    /// The size of the image when encoded as png, if it was encoded
+    pub png_bytes: Option<usize>,
+    /// The size of the raw image data when downscaled to the needed size
+    pub downscaled_bytes: usize,
+}
+
+impl ImageAnalysis {
+    /// Returns true if the image data is larger than needed
+    pub fn is_oversized(&self) -> bool {
+        self.downscaled_bytes < self.data_bytes
+    }
+
+    /// The downscaled pixel size, keeping the aspect ratio of the image
+    pub fn downscaled_size(&self) -> (u32, u32) {
+        let factor = (f64::from(self.needed_width) / f64::from(self.pixel_width))
+            .min(f64::from(self.needed_height) / f64::from(self.pixel_height))
+            .min(1.0);
+
+        (
+            ((f64::from(self.pixel_width) * factor).round() as u32).max(1),
+            ((f64::from(self.pixel_height) * factor).round() as u32).max(1),
+        )
+    }
+}
+
+/// Analyzes the size of the bitmap images in the document.
+/// The needed size of the images is calculated for the given dpi, or the dpi of the document format.
+/// The images are only encoded as png to determine their encoded size with `encode_png`, as it is expensive.
+pub fn analyze_images(
+    document: &RnotefileWrapper,
+    dpi: Option<f64>,
+    encode_png: bool,
+) -> Vec<ImageAnalysis> {
+    let scale = dpi.unwrap_or(document.document().format.dpi) / document.document().format.dpi;
+
+    document
+        .store_snapshot()
+        .stroke_components()
+        .iter()
+        .enumerate()
+        .filter_map(|(index, slot)| match &slot.value {
+            Some(Stroke::BitmapImage(bitmapimage)) => Some((index, bitmapimage)),
+            _ => None,
+        })
+        .map(|(index, bitmapimage)| {
+            let image = &bitmapimage.image;
+            let needed_size = bitmapimage.rectangle.size() * scale;
+
+            let mut analysis = ImageAnalysis {
+                index,
+                pixel_width: image.pixel_width,
+                pixel_height: image.pixel_height,
+                needed_width: (needed_size[0].ceil() as u32).max(1),
+                needed_height: (needed_size[1].ceil() as u32).max(1),
+                data_bytes: image.data.len(),
+                base64_bytes: image.data.len().div_ceil(3) * 4,
+                png_bytes: encode_png
+                    .then(|| {
+                        image
+                            .clone()
+                            .into_encoded_bytes(image::ImageOutputFormat::Png)
+                            .ok()
+                    })
+                    .flatten()
+                    .map(|bytes| bytes.len()),
+                downscaled_bytes: 0,
+            };
+            let (width, height) = analysis.downscaled_size();
+            analysis.downscaled_bytes = 4 * width as usize * height as usize;
+
+            analysis
+        })
+        .collect()
+}
+
+/// Downscales the bitmap images which are larger than needed to be displayed at the given dpi,
+/// or the dpi of the document format. Returns the analysis of the downscaled images.
+pub fn downscale_images(
+    document: &mut RnotefileWrapper,
+    dpi: Option<f64>,
+) -> anyhow::Result<Vec<ImageAnalysis>> {
+    if let Some(dpi) = dpi.filter(|dpi| !(dpi.is_finite() && *dpi > 0.0)) {
+        return Err(anyhow!("the dpi must be positive, got {dpi}"));
+    }
+    let oversized = analyze_images(document, dpi, false)
+        .into_iter()
+        .filter(|analysis| analysis.is_oversized())
+        .collect::<Vec<ImageAnalysis>>();
+
+    for analysis in oversized.iter() {
+        let (width, height) = analysis.downscaled_size();
+
+        if let Some(Stroke::BitmapImage(bitmapimage)) =
+            document.store_snapshot_mut().stroke_mut(analysis.index)
+        {
+            bitmapimage.image.resize(width, height)?;
+        }
+    }
+
+    Ok(oversized)
+}
+
+/// Bitmap images with identical image data
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateImages {
+    /// The content hash of the image data
+    pub hash: String,
+    /// The slot indices of the images, in ascending order
+    pub indices: Vec<usize>,
+    /// The size of the raw image data of a single image
+    pub data_bytes: usize,
+}
+
+impl DuplicateImages {
+    /// The size of the raw image data which is stored more than once
+    pub fn redundant_bytes(&self) -> usize {
+        self.data_bytes * (self.indices.len() - 1)
+    }
+}
+
+/// Finds the bitmap images with identical image data, by their content hash.
+/// Images with a hash collision are compared by their data.
+pub fn find_duplicate_images(document: &RnotefileWrapper) -> Vec<DuplicateImages> {
+    let mut groups: Vec<(u64, Vec<(usize, &Image)>)> = vec![];
+    let mut groups_by_hash: HashMap<u64, Vec<usize>> = HashMap::new();
+
+    for (index, image) in document
+        .store_snapshot()
+        .stroke_components()
+        .iter()
+        .enumerate()
+        .filter_map(|(index, slot)| match &slot.value {
+            Some(Stroke::BitmapImage(bitmapimage)) => Some((index, &bitmapimage.image)),
+            _ => None,
+        })
+    {
+        let hash = image_hash(image);
+        let candidates = groups_by_hash.entry(hash).or_default();
+
+        match candidates
+            .iter()
+            .find(|&&group| same_image_data(groups[group].1[0].1, image))
+        {
+            Some(&group) => groups[group].1.push((index, image)),
+            None => {
+                candidates.push(groups.len());
+                groups.push((hash, vec![(index, image)]));
+            }
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter(|(_, images)| images.len() > 1)
+        .map(|(hash, images)| DuplicateImages {
+            hash: format!("{hash:016x}"),
+            data_bytes: images[0].1.data.len(),
+            indices: images.into_iter().map(|(index, _)| index).collect(),
+        })
+        .collect()
+}
+
+fn image_hash(image: &Image) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    image.pixel_width.hash(&mut hasher);
+    image.pixel_height.hash(&mut hasher);
+    image.memory_format.hash(&mut hasher);
+    image.data.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn same_image_data(a: &Image, b: &Image) -> bool {
+    a.pixel_width == b.pixel_width
+        && a.pixel_height == b.pixel_height
+        && a.memory_format == b.memory_format
+        && a.data == b.data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rnotev0_4::Background;
+    use crate::rnotev0_5::stroke::BitmapImage;
+    use crate::rnotev0_5::tests::roundtrip;
+    use crate::rnotev0_5::{DocumentBuilder, Format, Layout};
+
+    /// A document with images of the pixel sizes, each displayed with a width of 10 and a height of 5
+    fn document(pixel_sizes: &[(u32, u32)]) -> RnotefileWrapper {
+        let mut builder =
+            DocumentBuilder::new(Format::default(), Layout::Infinite, Background::default());
+        for (i, &(width, height)) in pixel_sizes.iter().enumerate() {
+            let mut bytes = vec![];
+            image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+                width,
+                height,
+                image::Rgba([255, 0, 0, 255]),
+            ))
+            .write_to(&mut bytes, image::ImageOutputFormat::Png)
+            .unwrap();
+            let mut bitmapimage = BitmapImage::import_from_image_bytes(
+                &bytes,
+                image::ImageFormat::Png,
+                na::vector![i as f64 * 20.0, 0.0],
+                Some(10.0),
+            )
+            .unwrap();
+            // distort the aspect ratio, the needed size follows the displayed size
+            bitmapimage.rectangle.cuboid.half_extents = na::vector![5.0, 2.5];
+            builder.add_image(bitmapimage);
+        }
+        builder.build()
+    }
+
+    #[test]
+    fn images_are_analyzed_at_the_dpi() {
+        let document = document(&[(100, 50), (4, 2)]);
+
+        let analysis = analyze_images(&document, Some(192.0), false);
+
+        assert_eq!(analysis.len(), 2);
+        assert_eq!(
+            (analysis[0].needed_width, analysis[0].needed_height),
+            (20, 10)
+        );
+        assert!(analysis[0].is_oversized());
+        assert_eq!(analysis[0].downscaled_size(), (20, 10));
+        assert_eq!(analysis[0].data_bytes, 100 * 50 * 4);
+        assert!(!analysis[1].is_oversized());
+        assert!(analysis.iter().all(|analysis| analysis.png_bytes.is_none()));
+        assert!(analyze_images(&document, None, true)
+            .iter()
+            .all(|analysis| analysis.png_bytes.is_some()));
+    }
+
+    #[test]
+    fn oversized_images_are_downscaled() {
+        let mut document = document(&[(100, 50), (4, 2)]);
+
+        let downscaled = downscale_images(&mut document, None).unwrap();
+        let loaded = roundtrip(&document);
+        let sizes = analyze_images(&loaded, None, false)
+            .iter()
+            .map(|analysis| (analysis.pixel_width, analysis.pixel_height))
+            .collect::<Vec<(u32, u32)>>();
+
+        assert_eq!(downscaled.len(), 1);
+        assert_eq!(sizes, vec![(10, 5), (4, 2)]);
+    }
+
+    #[test]
+    fn invalid_dpi_is_rejected() {
+        for dpi in [0.0, -96.0, f64::NAN, f64::INFINITY] {
+            let mut document = document(&[(100, 50)]);
+
+            assert!(downscale_images(&mut document, Some(dpi)).is_err());
+            assert_eq!(analyze_images(&document, None, false)[0].pixel_width, 100);
+        }
+    }
+
+    #[test]
+    fn identical_images_are_duplicates() {
+        let document = document(&[(4, 2), (8, 2), (4, 2)]);
+
+        let duplicates = find_duplicate_images(&document);
+
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].indices, vec![1, 3]);
+        assert_eq!(duplicates[0].redundant_bytes(), 4 * 2 * 4);
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use p2d::bounding_volume::AABB;
+use serde::Serialize;
+
+use super::images::{self, DuplicateImages, ImageAnalysis};
+use crate::rnotev0_5::{Format, Layout, RnotefileWrapper};
+
+/// Information about a document
+#[derive(Debug, Clone, Serialize)]
+pub struct DocumentInfo {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub layout: Layout,
+    /// The bounds of the strokes which are not trashed
+    pub content_bounds: Option<AABB>,
+    pub format: Format,
+    /// The number of strokes by their type
+    pub strokes: BTreeMap<&'static str, usize>,
+    /// The number of trashed strokes
+    pub trashed: usize,
+    /// The number of selected strokes
+    pub selected: usize,
+    /// The size analysis of the bitmap images
+    pub images: Vec<ImageAnalysis>,
+    /// The bitmap images with identical image data
+    pub duplicate_images: Vec<DuplicateImages>,
+}
+
+impl DocumentInfo {
+    /// Collects the information about the document.
+    /// The needed size of the images is analyzed for the given dpi, or the dpi of the document format.
+    pub fn new(document: &RnotefileWrapper, dpi: Option<f64>) -> Self {
+        let doc = document.document();
+        let store = document.store_snapshot();
+
+        let mut strokes = BTreeMap::new();
+        for stroke in store
+            .stroke_components()
+            .iter()
+            .filter_map(|slot| slot.value.as_ref())
+        {
+            *strokes.entry(stroke.type_name()).or_insert(0) += 1;
+        }
+
+        Self {
+            x: doc.x,
+            y: doc.y,
+            width: doc.width,
+            height: doc.height,
+            layout: doc.layout(),
+            content_bounds: store.strokes_bounds(),
+            format: doc.format,
+            strokes,
+            trashed: (0..store.stroke_components().len())
+                .filter(|&index| store.is_trashed(index))
+                .count(),
+            selected: store
+                .selection_components()
+                .iter()
+                .filter_map(|slot| slot.value)
+                .filter(|selection| selection.selected)
+                .count(),
+            images: images::analyze_images(document, dpi, true),
+            duplicate_images: images::find_duplicate_images(document),
+        }
+    }
+}
+
+impl fmt::Display for DocumentInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "document: {} x {} at ({}, {}), {:?} layout",
+            self.width, self.height, self.x, self.y, self.layout
+        )?;
+        if let Some(bounds) = self.content_bounds {
+            writeln!(
+                f,
+                "content: ({:.1}, {:.1}) to ({:.1}, {:.1})",
+                bounds.mins[0], bounds.mins[1], bounds.maxs[0], bounds.maxs[1]
+            )?;
+        }
+        writeln!(
+            f,
+            "format: {} x {} at {} dpi, {:?}",
+            self.format.width, self.format.height, self.format.dpi, self.format.orientation
+        )?;
+        writeln!(
+            f,
+            "strokes: {} ({}), {} trashed, {} selected",
+            self.strokes.values().sum::<usize>(),
+            self.strokes
+                .iter()
+                .map(|(stroke_type, count)| format!("{count} {stroke_type}"))
+                .collect::<Vec<String>>()
+                .join(", "),
+            self.trashed,
+            self.selected
+        )?;
+
+        if !self.images.is_empty() {
+            writeln!(f, "bitmap images:")?;
+        }
+        for image in self.images.iter() {
+            let png = image
+                .png_bytes
+                .map(|bytes| format!("{bytes} B"))
+                .unwrap_or_else(|| String::from("-"));
+
+            writeln!(
+                f,
+                "  slot {}: {} x {} px, needs {} x {} px, {} B data, {} B base64, {png} as png, {} B downscaled",
+                image.index,
+                image.pixel_width,
+                image.pixel_height,
+                image.needed_width,
+                image.needed_height,
+                image.data_bytes,
+                image.base64_bytes,
+                image.downscaled_bytes
+            )?;
+        }
+
+        for duplicate in self.duplicate_images.iter() {
+            writeln!(
+                f,
+                "duplicate images in slots {}: {} B stored redundantly",
+                duplicate
+                    .indices
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<String>>()
+                    .join(", "),
+                duplicate.redundant_bytes()
+            )?;
+        }
+
+        Ok(())
+    }
+}
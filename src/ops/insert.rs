@@ -0,0 +1,203 @@
+use anyhow::anyhow;
+
+use crate::rnotev0_5::stroke::{BitmapImage, Stroke, VectorImage};
+use crate::rnotev0_5::{RnotefileWrapper, ShapeBehaviour};
+
+/// An encoded image to insert
+#[derive(Debug, Clone, Copy)]
+pub enum ImageData<'a> {
+    /// A png, jpeg or other bitmap image
+    Bitmap(&'a [u8], image::ImageFormat),
+    /// A svg
+    Svg(&'a str),
+}
+
+/// The placement of an inserted image
+#[derive(Debug, Clone, Default)]
+pub struct InsertOptions {
+    /// The position of the upper left corner of the image.
+    /// Defaults to the position of the replaced image, or the document origin offset by `IMPORT_OFFSET_DEFAULT`.
+    pub position: Option<na::Vector2<f64>>,
+    /// The width of the image, the height is determined by the aspect ratio of the image. Must be positive.
+    /// Defaults to the width of the replaced image, or the size of the image.
+    pub width: Option<f64>,
+    /// Replace the image in this slot instead of inserting a new stroke
+    pub replace: Option<usize>,
+}
+
+/// Inserts the image into the document, or replaces an existing image.
+/// Returns the slot index of the image.
+pub fn insert_image(
+    document: &mut RnotefileWrapper,
+    image: ImageData,
+    options: &InsertOptions,
+) -> anyhow::Result<usize> {
+    let replaced_bounds = options
+        .replace
+        .map(|index| {
+            match document
+                .store_snapshot()
+                .stroke_components()
+                .get(index)
+                .and_then(|slot| slot.value.as_ref())
+            {
+                Some(stroke @ (Stroke::BitmapImage(_) | Stroke::VectorImage(_))) => {
+                    Ok(stroke.bounds())
+                }
+                Some(stroke) => Err(anyhow!(
+                    "the stroke in slot {index} is a {}, not an image",
+                    stroke.type_name()
+                )),
+                None => Err(anyhow!("there is no stroke in slot {index}")),
+            }
+        })
+        .transpose()?;
+
+    let origin = na::vector![document.document().x, document.document().y];
+    let width = options
+        .width
+        .or_else(|| replaced_bounds.map(|bounds| bounds.extents()[0]));
+    if let Some(width) = width.filter(|width| !(width.is_finite() && *width > 0.0)) {
+        return Err(anyhow!(
+            "the width of the image must be positive, got {width}"
+        ));
+    }
+
+    let stroke = match image {
+        ImageData::Bitmap(bytes, format) => {
+            let pos = options.position.unwrap_or_else(|| {
+                replaced_bounds.map_or(origin + BitmapImage::IMPORT_OFFSET_DEFAULT, |bounds| {
+                    bounds.mins.coords
+                })
+            });
+            Stroke::BitmapImage(BitmapImage::import_from_image_bytes(
+                bytes, format, pos, width,
+            )?)
+        }
+        ImageData::Svg(svg_data) => {
+            let pos = options.position.unwrap_or_else(|| {
+                replaced_bounds.map_or(origin + VectorImage::IMPORT_OFFSET_DEFAULT, |bounds| {
+                    bounds.mins.coords
+                })
+            });
+            Stroke::VectorImage(VectorImage::import_from_svg_data(svg_data, pos, width)?)
+        }
+    };
+
+    let store = document.store_snapshot_mut();
+    match options.replace {
+        Some(index) => {
+            store.replace_stroke(index, stroke);
+            Ok(index)
+        }
+        None => Ok(store.insert_stroke(stroke)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rnotev0_4::Background;
+    use crate::rnotev0_5::tests::{line_stroke, roundtrip};
+    use crate::rnotev0_5::{DocumentBuilder, Format, Layout};
+
+    fn png(pixel: [u8; 4], width: u32, height: u32) -> Vec<u8> {
+        let imgbuf = image::RgbaImage::from_pixel(width, height, image::Rgba(pixel));
+        let mut bytes = vec![];
+        image::DynamicImage::ImageRgba8(imgbuf)
+            .write_to(&mut bytes, image::ImageOutputFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    fn document() -> RnotefileWrapper {
+        let mut builder =
+            DocumentBuilder::new(Format::default(), Layout::Infinite, Background::default());
+        builder.add_stroke(line_stroke(na::vector![0.0, 0.0], na::vector![10.0, 10.0]));
+        builder.build()
+    }
+
+    #[test]
+    fn replaced_image_can_be_loaded() {
+        let mut document = document();
+        let red = png([255, 0, 0, 255], 4, 2);
+        let blue = png([0, 0, 255, 255], 2, 2);
+        let index = insert_image(
+            &mut document,
+            ImageData::Bitmap(&red, image::ImageFormat::Png),
+            &InsertOptions {
+                position: Some(na::vector![100.0, 50.0]),
+                width: Some(40.0),
+                ..InsertOptions::default()
+            },
+        )
+        .unwrap();
+
+        let replaced = insert_image(
+            &mut document,
+            ImageData::Bitmap(&blue, image::ImageFormat::Png),
+            &InsertOptions {
+                replace: Some(index),
+                ..InsertOptions::default()
+            },
+        )
+        .unwrap();
+        let loaded = roundtrip(&document);
+        let (key, stroke, _, _, chrono) = loaded
+            .strokes()
+            .find(|(key, ..)| key.index == index)
+            .unwrap();
+
+        assert_eq!(replaced, index);
+        assert_eq!(key.version % 2, 1);
+        assert_eq!(chrono, loaded.store_snapshot().chrono_counter());
+        match stroke {
+            Stroke::BitmapImage(bitmapimage) => {
+                assert_eq!(bitmapimage.image.pixel_width, 2);
+                assert_eq!(&bitmapimage.image.data[..4], &[0, 0, 255, 255]);
+                // the position and width of the replaced image are kept, the height follows the aspect ratio
+                let bounds = stroke.bounds();
+                assert!((bounds.mins - na::point![100.0, 50.0]).norm() < 1e-9);
+                assert!((bounds.extents() - na::vector![40.0, 40.0]).norm() < 1e-9);
+            }
+            _ => panic!("expected a bitmap image, got a {}", stroke.type_name()),
+        }
+    }
+
+    #[test]
+    fn invalid_widths_are_rejected() {
+        let red = png([255, 0, 0, 255], 1, 1);
+
+        for width in [0.0, -10.0, f64::NAN, f64::INFINITY] {
+            let mut document = document();
+            let result = insert_image(
+                &mut document,
+                ImageData::Bitmap(&red, image::ImageFormat::Png),
+                &InsertOptions {
+                    width: Some(width),
+                    ..InsertOptions::default()
+                },
+            );
+
+            assert!(result.is_err(), "width {width} was accepted");
+            assert_eq!(document.strokes().count(), 1);
+        }
+    }
+
+    #[test]
+    fn only_images_can_be_replaced() {
+        let mut document = document();
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="10"></svg>"#;
+
+        let result = insert_image(
+            &mut document,
+            ImageData::Svg(svg),
+            &InsertOptions {
+                replace: Some(1),
+                ..InsertOptions::default()
+            },
+        );
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,116 @@
+use anyhow::anyhow;
+
+use crate::rnotev0_4::Background;
+use crate::rnotev0_5::{Format, RnotefileWrapper, TransformBehaviour};
+
+/// Options for merging documents
+#[derive(Debug, Clone, Default)]
+pub struct MergeOptions {
+    /// The format of the merged document. Taken from the first document when None.
+    pub format: Option<Format>,
+    /// The background of the merged document. Taken from the first document when None.
+    pub background: Option<Background>,
+}
+
+/// Merges the documents into one.
+///
+/// The strokes of every document are placed vertically below the extents of the previous documents
+/// and are ordered chronologically after their strokes.
+pub fn merge(
+    documents: Vec<RnotefileWrapper>,
+    options: &MergeOptions,
+) -> anyhow::Result<RnotefileWrapper> {
+    let mut documents = documents.into_iter();
+    let (mut document, mut store) = documents
+        .next()
+        .ok_or_else(|| anyhow!("no documents to merge"))?
+        .into_parts();
+
+    for next in documents {
+        let (next_document, mut next_store) = next.into_parts();
+        let offset = na::vector![
+            document.x - next_document.x,
+            document.y + document.height - next_document.y
+        ];
+
+        next_store
+            .strokes_mut()
+            .for_each(|stroke| stroke.translate(offset));
+        store.append(next_store);
+
+        document.width = document.width.max(next_document.width);
+        document.height += next_document.height;
+    }
+
+    if let Some(format) = options.format {
+        document.format = format;
+    }
+    if let Some(background) = options.background.clone() {
+        document.background = background;
+    }
+
+    Ok(RnotefileWrapper::new(document, store))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rnotev0_5::tests::{line_stroke, roundtrip};
+    use crate::rnotev0_5::{DocumentBuilder, Layout, ShapeBehaviour};
+
+    fn document(strokes: usize) -> RnotefileWrapper {
+        let mut builder =
+            DocumentBuilder::new(Format::default(), Layout::FixedSize, Background::default());
+        for i in 0..strokes {
+            let offset = na::vector![0.0, i as f64 * 10.0 + 100.0];
+            builder.add_stroke(line_stroke(offset, offset + na::vector![100.0, 0.0]));
+        }
+        builder.build()
+    }
+
+    #[test]
+    fn merged_document_can_be_loaded() {
+        let merged = merge(vec![document(2), document(1)], &MergeOptions::default()).unwrap();
+
+        let loaded = roundtrip(&merged);
+        let entries = loaded
+            .strokes()
+            .map(|(_, stroke, _, _, chrono)| (stroke.bounds().center()[1], chrono))
+            .collect::<Vec<(f64, u32)>>();
+        let page_height = Format::default().height;
+
+        // the strokes of the second document are placed below the first and ordered after its strokes
+        assert_eq!(
+            entries,
+            vec![(100.0, 1), (110.0, 2), (page_height + 100.0, 3)]
+        );
+        assert_eq!(loaded.document().height, 2.0 * page_height);
+        assert_eq!(loaded.store_snapshot().chrono_counter(), 3);
+    }
+
+    #[test]
+    fn format_and_background_can_be_overridden() {
+        let format = Format {
+            width: 500.0,
+            ..Format::default()
+        };
+        let background = Background {
+            color: crate::rnotev0_5::Color::BLACK,
+            ..Background::default()
+        };
+        let options = MergeOptions {
+            format: Some(format),
+            background: Some(background.clone()),
+        };
+
+        let merged = merge(vec![document(1), document(1)], &options).unwrap();
+
+        assert_eq!(merged.document().format, format);
+        assert_eq!(merged.document().background, background);
+    }
+
+    #[test]
+    fn merging_nothing_fails() {
+        assert!(merge(vec![], &MergeOptions::default()).is_err());
+    }
+}
@@ -0,0 +1,151 @@
+use serde::Serialize;
+
+use crate::rnotev0_5::RnotefileWrapper;
+
+/// Options for removing transient editor state from a document before it is written
+#[derive(Debug, Clone, Copy)]
+pub struct Normalize {
+    /// Deselects all strokes
+    pub clear_selection: bool,
+    /// Renumbers the chrono components of the strokes from 1, preserving their order
+    pub reset_chrono: bool,
+    /// Removes trashed strokes and compacts the slots of the store
+    pub purge_trashed: bool,
+}
+
+impl Default for Normalize {
+    fn default() -> Self {
+        Self {
+            clear_selection: true,
+            reset_chrono: false,
+            purge_trashed: false,
+        }
+    }
+}
+
+/// The result of normalizing a document
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NormalizeReport {
+    /// The number of deselected strokes
+    pub deselected: usize,
+    /// The number of removed trashed strokes
+    pub removed_trashed: usize,
+}
+
+impl Normalize {
+    /// Normalizes nothing
+    pub fn none() -> Self {
+        Self {
+            clear_selection: false,
+            reset_chrono: false,
+            purge_trashed: false,
+        }
+    }
+
+    /// Applies the normalization to the document
+    pub fn apply(&self, document: &mut RnotefileWrapper) -> NormalizeReport {
+        let store = document.store_snapshot_mut();
+        let mut report = NormalizeReport::default();
+
+        if self.purge_trashed {
+            // compacting also renumbers the chrono components
+            (report.removed_trashed, _) = store.compact();
+        } else if self.reset_chrono {
+            store.renumber_chrono();
+        }
+        if self.clear_selection {
+            report.deselected = store.clear_selection();
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rnotev0_4::Background;
+    use crate::rnotev0_5::tests::{line_stroke, roundtrip};
+    use crate::rnotev0_5::{DocumentBuilder, Format, Layout};
+
+    /// A document with three strokes, the first one selected and the second one trashed
+    fn document() -> RnotefileWrapper {
+        let mut builder =
+            DocumentBuilder::new(Format::default(), Layout::Infinite, Background::default());
+        let indices = (0..3)
+            .map(|i| {
+                let offset = na::vector![0.0, f64::from(i) * 10.0];
+                builder.add_stroke(line_stroke(offset, offset + na::vector![100.0, 0.0]))
+            })
+            .collect::<Vec<usize>>();
+        let mut document = builder.build();
+        let store = document.store_snapshot_mut();
+        store.set_selected(indices[0], true);
+        store.set_trashed(indices[1], true);
+        document
+    }
+
+    #[test]
+    fn purged_document_can_be_loaded() {
+        let mut document = document();
+        let normalize = Normalize {
+            purge_trashed: true,
+            ..Normalize::default()
+        };
+
+        let report = normalize.apply(&mut document);
+        let loaded = roundtrip(&document);
+
+        assert_eq!((report.deselected, report.removed_trashed), (1, 1));
+        assert_eq!(
+            loaded
+                .strokes()
+                .map(|(key, _, trashed, selected, chrono)| (key.index, trashed, selected, chrono))
+                .collect::<Vec<_>>(),
+            vec![(1, false, false, 1), (2, false, false, 2)]
+        );
+    }
+
+    #[test]
+    fn default_only_clears_the_selection() {
+        let mut document = document();
+
+        let report = Normalize::default().apply(&mut document);
+        let loaded = roundtrip(&document);
+
+        assert_eq!((report.deselected, report.removed_trashed), (1, 0));
+        assert_eq!(
+            loaded
+                .strokes()
+                .map(|(_, _, trashed, selected, _)| (trashed, selected))
+                .collect::<Vec<_>>(),
+            vec![(false, false), (true, false), (false, false)]
+        );
+    }
+
+    #[test]
+    fn renumbering_fills_missing_chrono_components() {
+        let mut json = serde_json::to_value(document()).unwrap();
+        json["data"]["store_snapshot"]["chrono_components"]
+            .as_array_mut()
+            .unwrap()
+            .truncate(2);
+        let mut document: RnotefileWrapper = serde_json::from_value(json).unwrap();
+        let normalize = Normalize {
+            reset_chrono: true,
+            ..Normalize::none()
+        };
+
+        normalize.apply(&mut document);
+        let loaded = roundtrip(&document);
+
+        // strokes without a chrono component are treated as the oldest
+        assert_eq!(
+            loaded
+                .strokes()
+                .map(|(_, _, _, _, chrono)| chrono)
+                .collect::<Vec<u32>>(),
+            vec![3, 1, 2]
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use serde::Serialize;
+
+use crate::rnotev0_5::stroke::Stroke;
+use crate::rnotev0_5::RnotefileWrapper;
+
+/// A curve which remaps pen pressures. The results are clamped to the range [0.0, 1.0].
+#[derive(Debug, Clone, PartialEq)]
+pub enum PressureCurve {
+    /// Raises the pressure to the power of gamma. Values below 1.0 make light strokes heavier.
+    Gamma(f64),
+    /// Maps the range linearly to [0.0, 1.0], clamping pressures outside of it
+    LinearRange { min: f64, max: f64 },
+    /// Interpolates linearly between the `(input, output)` points, sorted by input.
+    /// Pressures outside of the inputs are mapped to the output of the nearest point.
+    Table(Vec<(f64, f64)>),
+}
+
+impl PressureCurve {
+    /// Validates the curve parameters
+    pub fn validate(&self) -> anyhow::Result<()> {
+        match self {
+            Self::Gamma(gamma) if !(gamma.is_finite() && *gamma > 0.0) => {
+                Err(anyhow!("the gamma must be positive, got {gamma}"))
+            }
+            Self::LinearRange { min, max } if min.partial_cmp(max) != Some(Ordering::Less) => {
+                Err(anyhow!(
+                    "the minimum of the range must be smaller than the maximum, got [{min}, {max}]"
+                ))
+            }
+            Self::Table(points) if points.is_empty() => {
+                Err(anyhow!("the pressure table has no points"))
+            }
+            Self::Table(points) if points.windows(2).any(|pair| pair[0].0 >= pair[1].0) => Err(
+                anyhow!("the inputs of the pressure table must be strictly increasing"),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /// Maps the pressure through the curve
+    pub fn map(&self, pressure: f64) -> f64 {
+        let mapped = match self {
+            Self::Gamma(gamma) => pressure.clamp(0.0, 1.0).powf(*gamma),
+            Self::LinearRange { min, max } => (pressure - min) / (max - min),
+            Self::Table(points) => {
+                let upper = points.partition_point(|(input, _)| *input < pressure);
+                match (
+                    upper.checked_sub(1).map(|i| points[i]),
+                    points.get(upper).copied(),
+                ) {
+                    (Some((x0, y0)), Some((x1, y1))) => {
+                        y0 + (y1 - y0) * (pressure - x0) / (x1 - x0)
+                    }
+                    (Some((_, y)), None) | (None, Some((_, y))) => y,
+                    (None, None) => pressure,
+                }
+            }
+        };
+
+        mapped.clamp(0.0, 1.0)
+    }
+
+    /// Parses a table in the form `<input>:<output>,<input>:<output>,..`
+    pub fn parse_table(s: &str) -> anyhow::Result<Self> {
+        let points = s
+            .split(',')
+            .map(|point| {
+                let (input, output) = point.split_once(':').ok_or_else(|| {
+                    anyhow!("expected a point in the form `<input>:<output>`, got '{point}'")
+                })?;
+                Ok((f64::from_str(input.trim())?, f64::from_str(output.trim())?))
+            })
+            .collect::<anyhow::Result<Vec<(f64, f64)>>>()?;
+
+        Ok(Self::Table(points))
+    }
+}
+
+/// The result of remapping the pressures
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct PressureReport {
+    /// The number of remapped brush strokes
+    pub strokes: usize,
+    /// The number of elements whose pressure changed
+    pub changed_elements: usize,
+}
+
+/// Remaps the pressures of the elements of all brush strokes through the curve.
+pub fn remap_pressure(
+    document: &mut RnotefileWrapper,
+    curve: &PressureCurve,
+) -> anyhow::Result<PressureReport> {
+    curve.validate()?;
+    let mut report = PressureReport::default();
+
+    for stroke in document.store_snapshot_mut().strokes_mut() {
+        let brushstroke = match stroke {
+            Stroke::BrushStroke(brushstroke) => brushstroke,
+            _ => continue,
+        };
+        report.strokes += 1;
+
+        for element in brushstroke
+            .path
+            .iter_mut()
+            .flat_map(|segment| segment.elements_mut())
+        {
+            let pressure = curve.map(element.pressure);
+            if pressure != element.pressure {
+                element.set_pressure_clamped(pressure);
+                report.changed_elements += 1;
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rnotev0_4::Background;
+    use crate::rnotev0_5::tests::{line_stroke, rectangle_stroke};
+    use crate::rnotev0_5::{DocumentBuilder, Format, Layout};
+
+    #[test]
+    fn curves_map_and_clamp_the_pressure() {
+        let gamma = PressureCurve::Gamma(2.0);
+        assert_eq!(gamma.map(0.5), 0.25);
+        assert_eq!(gamma.map(1.5), 1.0);
+
+        let range = PressureCurve::LinearRange { min: 0.2, max: 0.6 };
+        assert!((range.map(0.4) - 0.5).abs() < 1e-9);
+        assert_eq!(range.map(0.1), 0.0);
+        assert_eq!(range.map(0.8), 1.0);
+
+        let table = PressureCurve::parse_table("0.2:0.1, 0.6:0.9").unwrap();
+        assert_eq!(table, PressureCurve::Table(vec![(0.2, 0.1), (0.6, 0.9)]));
+        assert!((table.map(0.4) - 0.5).abs() < 1e-9);
+        assert_eq!(table.map(0.6), 0.9);
+        // outside of the inputs the nearest point is used
+        assert_eq!(table.map(0.0), 0.1);
+        assert_eq!(table.map(1.0), 0.9);
+    }
+
+    #[test]
+    fn invalid_curves_are_rejected() {
+        assert!(PressureCurve::Gamma(0.0).validate().is_err());
+        assert!(PressureCurve::Gamma(f64::NAN).validate().is_err());
+        assert!(PressureCurve::LinearRange { min: 0.5, max: 0.5 }
+            .validate()
+            .is_err());
+        assert!(PressureCurve::Table(vec![]).validate().is_err());
+        assert!(PressureCurve::Table(vec![(0.5, 0.0), (0.5, 1.0)])
+            .validate()
+            .is_err());
+        assert!(PressureCurve::parse_table("0.5").is_err());
+        assert!(PressureCurve::parse_table("0.5:x").is_err());
+    }
+
+    #[test]
+    fn only_brush_strokes_are_remapped() {
+        let mut builder =
+            DocumentBuilder::new(Format::default(), Layout::Infinite, Background::default());
+        builder.add_stroke(line_stroke(na::vector![0.0, 0.0], na::vector![10.0, 0.0]));
+        builder.add_stroke(rectangle_stroke(
+            na::point![0.0, 0.0],
+            na::point![10.0, 10.0],
+        ));
+        let mut document = builder.build();
+
+        let report = remap_pressure(&mut document, &PressureCurve::Gamma(2.0)).unwrap();
+        assert_eq!(report.strokes, 1);
+        assert_eq!(report.changed_elements, 2);
+        match document.strokes().next().unwrap().1 {
+            Stroke::BrushStroke(brushstroke) => assert!(brushstroke
+                .path
+                .iter()
+                .all(|segment| segment.start().pressure == 0.25 && segment.end().pressure == 0.25)),
+            stroke => panic!("unexpected stroke {stroke:?}"),
+        }
+
+        // the identity leaves the pressures unchanged
+        let report = remap_pressure(&mut document, &PressureCurve::Gamma(1.0)).unwrap();
+        assert_eq!(report.changed_elements, 0);
+    }
+}
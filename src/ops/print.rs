@@ -0,0 +1,164 @@
+use serde::Serialize;
+
+use crate::rnotev0_4::PatternStyle;
+use crate::rnotev0_5::stroke::Stroke;
+use crate::rnotev0_5::{Color, RnotefileWrapper};
+
+/// The ink which colors are converted to
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ink {
+    /// Converts colors to the gray with the same luminance
+    Grayscale,
+    /// Converts colors to the nearest color of the palette, keeping their alpha
+    Palette(Vec<Color>),
+}
+
+impl Ink {
+    pub fn convert(&self, color: Color) -> Color {
+        match self {
+            Self::Grayscale => color.to_grayscale(),
+            Self::Palette(palette) => palette
+                .iter()
+                .map(|ink| Color { a: color.a, ..*ink })
+                .min_by(|a, b| a.distance(&color).total_cmp(&b.distance(&color)))
+                .unwrap_or(color),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PrintOptions {
+    pub ink: Ink,
+    /// Removes the fill colors of shapes
+    pub remove_fills: bool,
+}
+
+/// The result of the print-friendly conversion
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct PrintReport {
+    /// The number of stroke colors and the format border color, if they were changed by the conversion
+    pub colors: usize,
+    /// The number of removed fill colors
+    pub removed_fills: usize,
+    /// The number of converted bitmap images
+    pub bitmap_images: usize,
+    /// The number of vector images, which are left unchanged
+    pub vector_images: usize,
+}
+
+/// Converts the document into a printer friendly copy.
+///
+/// The stroke colors, the format border color and the data of bitmap images are converted to the ink, and the background
+/// is set to white without a pattern. The colors of vector images are not converted.
+pub fn print_friendly(
+    document: &mut RnotefileWrapper,
+    options: &PrintOptions,
+) -> anyhow::Result<PrintReport> {
+    let mut report = PrintReport::default();
+
+    for stroke in document.store_snapshot_mut().strokes_mut() {
+        let is_shape = matches!(stroke, Stroke::ShapeStroke(_));
+        match stroke {
+            Stroke::BrushStroke(_) | Stroke::ShapeStroke(_) => {}
+            Stroke::BitmapImage(bitmapimage) => {
+                bitmapimage
+                    .image
+                    .map_colors(|color| options.ink.convert(color))?;
+                report.bitmap_images += 1;
+            }
+            Stroke::VectorImage(_) => report.vector_images += 1,
+        }
+
+        if let Some(style) = stroke.style_mut() {
+            if options.remove_fills && is_shape && style.take_fill_color().is_some() {
+                report.removed_fills += 1;
+            }
+            for color in style.colors_mut() {
+                report.colors += convert_color(&options.ink, color);
+            }
+        }
+    }
+
+    let doc = document.document_mut();
+    report.colors += convert_color(&options.ink, &mut doc.format.border_color);
+    doc.background.color = Color::WHITE;
+    doc.background.pattern = PatternStyle::None;
+
+    Ok(report)
+}
+
+/// Converts the color to the ink, returning 1 if it was changed and 0 otherwise
+fn convert_color(ink: &Ink, color: &mut Color) -> usize {
+    let new_color = ink.convert(*color);
+    if new_color == *color {
+        return 0;
+    }
+
+    *color = new_color;
+    1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rnotev0_4::Background;
+    use crate::rnotev0_5::stroke::Style;
+    use crate::rnotev0_5::tests::{line_stroke, rectangle_stroke};
+    use crate::rnotev0_5::{DocumentBuilder, Format, Layout};
+
+    /// A document with a black line and a blue filled rectangle
+    fn document() -> RnotefileWrapper {
+        let mut builder =
+            DocumentBuilder::new(Format::default(), Layout::Infinite, Background::default());
+        builder.add_stroke(line_stroke(na::vector![0.0, 0.0], na::vector![10.0, 0.0]));
+        let mut rectangle = rectangle_stroke(na::point![0.0, 10.0], na::point![10.0, 20.0]);
+        if let Some(Style::Smooth(options)) = rectangle.style_mut() {
+            options.stroke_color = Some(Color::BLUE);
+            options.fill_color = Some(Color::BLUE);
+        }
+        builder.add_stroke(rectangle);
+        builder.build()
+    }
+
+    #[test]
+    fn only_changed_colors_are_counted() {
+        let mut document = document();
+        let options = PrintOptions {
+            ink: Ink::Grayscale,
+            remove_fills: false,
+        };
+
+        let report = print_friendly(&mut document, &options).unwrap();
+
+        // the black line is already gray, the blue stroke and fill colors and the red border are converted
+        assert_eq!(report.colors, 3);
+        assert_eq!(report.removed_fills, 0);
+        let format = document.document().format;
+        assert_eq!(format.border_color, Color::RED.to_grayscale());
+        let background = &document.document().background;
+        assert_eq!(background.color, Color::WHITE);
+        assert_eq!(background.pattern, PatternStyle::None);
+    }
+
+    #[test]
+    fn palette_converts_to_the_nearest_ink() {
+        let mut document = document();
+        let options = PrintOptions {
+            ink: Ink::Palette(vec![Color::BLACK, Color::RED]),
+            remove_fills: true,
+        };
+
+        let report = print_friendly(&mut document, &options).unwrap();
+
+        // the fill is removed before the conversion, the border is already red
+        assert_eq!(report.removed_fills, 1);
+        assert_eq!(report.colors, 1);
+        let colors: Vec<Color> = document
+            .store_snapshot_mut()
+            .strokes_mut()
+            .filter_map(|stroke| stroke.style_mut())
+            .flat_map(|style| style.colors_mut().into_iter().map(|color| *color))
+            .collect();
+        assert_eq!(colors, vec![Color::BLACK, Color::BLACK]);
+    }
+}
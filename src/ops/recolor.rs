@@ -0,0 +1,215 @@
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use serde::Serialize;
+
+use crate::rnotev0_5::{Color, RnotefileWrapper};
+
+/// Maps a color to another color
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorMapping {
+    pub from: Color,
+    pub to: Color,
+}
+
+impl FromStr for ColorMapping {
+    type Err = anyhow::Error;
+
+    /// Parses a mapping in the form `<from>=<to>`, with the colors as `#rrggbb` or `#rrggbbaa`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (from, to) = s.split_once('=').ok_or_else(|| {
+            anyhow!("expected a color mapping in the form `<from>=<to>`, got '{s}'")
+        })?;
+
+        Ok(Self {
+            from: from.parse()?,
+            to: to.parse()?,
+        })
+    }
+}
+
+/// A table of color mappings
+#[derive(Debug, Clone, Default)]
+pub struct ColorTable {
+    pub mappings: Vec<ColorMapping>,
+    /// The maximum distance (see `Color::distance()`) a color may have to the source color of a mapping to be mapped.
+    /// With a tolerance of 0.0 only exact matches are mapped.
+    pub tolerance: f64,
+}
+
+impl ColorTable {
+    /// Parses a table with one mapping per line. Empty lines are skipped.
+    pub fn parse(table: &str, tolerance: f64) -> anyhow::Result<Self> {
+        let mappings = table
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(ColorMapping::from_str)
+            .collect::<anyhow::Result<Vec<ColorMapping>>>()?;
+
+        Ok(Self {
+            mappings,
+            tolerance,
+        })
+    }
+
+    /// Maps the color to the target of the mapping whose source color is nearest, if it is within the tolerance
+    pub fn map(&self, color: &Color) -> Option<Color> {
+        self.mappings
+            .iter()
+            .map(|mapping| (mapping.from.distance(color), mapping))
+            .filter(|(distance, _)| *distance <= self.tolerance)
+            .min_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, mapping)| mapping.to)
+    }
+}
+
+/// The number of colors which were changed by recoloring
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct RecolorReport {
+    /// stroke and fill colors of strokes
+    pub strokes: usize,
+    /// the background color and the pattern color
+    pub background: usize,
+    /// the format border color
+    pub format: usize,
+}
+
+impl RecolorReport {
+    pub fn total(&self) -> usize {
+        self.strokes + self.background + self.format
+    }
+}
+
+/// Recolors every color of the document with the table.
+pub fn recolor(document: &mut RnotefileWrapper, table: &ColorTable) -> RecolorReport {
+    fn apply<'a>(table: &ColorTable, colors: impl IntoIterator<Item = &'a mut Color>) -> usize {
+        let mut changed = 0;
+        for color in colors {
+            if let Some(new_color) = table.map(color).filter(|new_color| new_color != color) {
+                *color = new_color;
+                changed += 1;
+            }
+        }
+        changed
+    }
+
+    let strokes = apply(
+        table,
+        document
+            .store_snapshot_mut()
+            .strokes_mut()
+            .filter_map(|stroke| stroke.style_mut())
+            .flat_map(|style| style.colors_mut()),
+    );
+
+    let doc = document.document_mut();
+    let background = apply(
+        table,
+        [&mut doc.background.color, &mut doc.background.pattern_color],
+    );
+    let format = apply(table, [&mut doc.format.border_color]);
+
+    RecolorReport {
+        strokes,
+        background,
+        format,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rnotev0_4::Background;
+    use crate::rnotev0_5::stroke::Style;
+    use crate::rnotev0_5::tests::line_stroke;
+    use crate::rnotev0_5::{DocumentBuilder, Format, Layout};
+
+    #[test]
+    fn table_is_parsed_line_by_line() {
+        let table = ColorTable::parse("#000000=#0000ff\n\n  #ffffff=#ff000080  \n", 0.0).unwrap();
+
+        assert_eq!(
+            table.mappings,
+            vec![
+                ColorMapping {
+                    from: Color::BLACK,
+                    to: Color::BLUE,
+                },
+                ColorMapping {
+                    from: Color::WHITE,
+                    to: Color {
+                        a: 128.0 / 255.0,
+                        ..Color::RED
+                    },
+                },
+            ]
+        );
+        assert!(ColorTable::parse("#000000", 0.0).is_err());
+        assert!(ColorTable::parse("#000000=blue", 0.0).is_err());
+    }
+
+    #[test]
+    fn nearest_mapping_within_the_tolerance_is_used() {
+        let table = ColorTable {
+            mappings: vec![
+                ColorMapping {
+                    from: Color::BLACK,
+                    to: Color::BLUE,
+                },
+                ColorMapping {
+                    from: Color::WHITE,
+                    to: Color::RED,
+                },
+            ],
+            tolerance: 0.2,
+        };
+        let dark_gray = Color {
+            r: 0.1,
+            g: 0.1,
+            b: 0.1,
+            a: 1.0,
+        };
+        let gray = Color {
+            r: 0.5,
+            g: 0.5,
+            b: 0.5,
+            a: 1.0,
+        };
+
+        assert_eq!(table.map(&dark_gray), Some(Color::BLUE));
+        assert_eq!(table.map(&gray), None);
+        assert_eq!(
+            ColorTable {
+                tolerance: 0.0,
+                ..table
+            }
+            .map(&dark_gray),
+            None
+        );
+    }
+
+    #[test]
+    fn only_changed_colors_are_counted() {
+        let mut builder =
+            DocumentBuilder::new(Format::default(), Layout::Infinite, Background::default());
+        builder.add_stroke(line_stroke(na::vector![0.0, 0.0], na::vector![10.0, 0.0]));
+        let mut document = builder.build();
+        let table =
+            ColorTable::parse("#000000=#0000ff\n#ffffff=#ffffff\n#ff0000=#000000", 0.0).unwrap();
+
+        let report = recolor(&mut document, &table);
+
+        // the white background is mapped to itself
+        assert_eq!(report.strokes, 1);
+        assert_eq!(report.background, 0);
+        assert_eq!(report.format, 1);
+        assert_eq!(report.total(), 2);
+        assert_eq!(document.document().format.border_color, Color::BLACK);
+        let (_, stroke, ..) = document.strokes().next().unwrap();
+        match stroke.style() {
+            Some(Style::Smooth(options)) => assert_eq!(options.stroke_color, Some(Color::BLUE)),
+            style => panic!("unexpected style {style:?}"),
+        }
+    }
+}
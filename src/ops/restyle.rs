@@ -0,0 +1,133 @@
+use crate::rnotev0_5::store::StrokeFilter;
+use crate::rnotev0_5::stroke::{Stroke, StyleKind};
+use crate::rnotev0_5::RnotefileWrapper;
+
+/// Converts the style of the brush strokes which match the filter to the kind, carrying over their width, colors and
+/// seed. See `Style::converted_to()`. With `from` only brush strokes with this kind of style are converted.
+///
+/// Returns the number of restyled brush strokes.
+pub fn restyle(
+    document: &mut RnotefileWrapper,
+    kind: StyleKind,
+    from: Option<StyleKind>,
+    filter: &StrokeFilter,
+) -> usize {
+    let store = document.store_snapshot_mut();
+    let indices = store
+        .strokes_filtered(filter)
+        .filter(|(_, stroke, _, _, _)| match stroke {
+            Stroke::BrushStroke(brushstroke) => {
+                let current = brushstroke.style.kind();
+                current != kind && from.is_none_or(|from| from == current)
+            }
+            _ => false,
+        })
+        .map(|(key, _, _, _, _)| key.index)
+        .collect::<Vec<usize>>();
+
+    for &index in indices.iter() {
+        if let Some(Stroke::BrushStroke(brushstroke)) = store.stroke_mut(index) {
+            brushstroke.style = brushstroke.style.converted_to(kind);
+        }
+    }
+
+    indices.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rnotev0_4::Background;
+    use crate::rnotev0_5::stroke::style::roughoptions::RoughOptions;
+    use crate::rnotev0_5::stroke::Style;
+    use crate::rnotev0_5::tests::{line_stroke, rectangle_stroke, roundtrip};
+    use crate::rnotev0_5::{Color, DocumentBuilder, Format, Layout};
+
+    /// A document with a smooth line, a rough blue line with a seed and a smooth rectangle
+    fn document() -> RnotefileWrapper {
+        let mut builder =
+            DocumentBuilder::new(Format::default(), Layout::Infinite, Background::default());
+        builder.add_stroke(line_stroke(na::vector![0.0, 0.0], na::vector![10.0, 0.0]));
+        let mut rough = line_stroke(na::vector![0.0, 10.0], na::vector![10.0, 10.0]);
+        if let Some(style) = rough.style_mut() {
+            *style = Style::Rough(RoughOptions {
+                stroke_width: 3.0,
+                stroke_color: Some(Color::BLUE),
+                seed: Some(42),
+                ..RoughOptions::default()
+            });
+        }
+        builder.add_stroke(rough);
+        builder.add_stroke(rectangle_stroke(
+            na::point![0.0, 20.0],
+            na::point![10.0, 30.0],
+        ));
+        builder.build()
+    }
+
+    fn kinds(document: &RnotefileWrapper) -> Vec<StyleKind> {
+        document
+            .strokes()
+            .filter_map(|(_, stroke, ..)| stroke.style().map(Style::kind))
+            .collect()
+    }
+
+    #[test]
+    fn only_brush_strokes_of_other_kinds_are_restyled() {
+        let mut document = document();
+
+        let restyled = restyle(
+            &mut document,
+            StyleKind::Textured,
+            None,
+            &StrokeFilter::default(),
+        );
+
+        assert_eq!(restyled, 2);
+        assert_eq!(
+            kinds(&document),
+            vec![StyleKind::Textured, StyleKind::Textured, StyleKind::Smooth]
+        );
+        roundtrip(&document);
+    }
+
+    #[test]
+    fn from_restricts_the_converted_kind() {
+        let mut document = document();
+
+        let restyled = restyle(
+            &mut document,
+            StyleKind::Smooth,
+            Some(StyleKind::Rough),
+            &StrokeFilter::default(),
+        );
+
+        assert_eq!(restyled, 1);
+        assert_eq!(
+            kinds(&document),
+            vec![StyleKind::Smooth, StyleKind::Smooth, StyleKind::Smooth]
+        );
+    }
+
+    #[test]
+    fn width_color_and_seed_are_carried_over() {
+        let mut document = document();
+
+        restyle(
+            &mut document,
+            StyleKind::Textured,
+            Some(StyleKind::Rough),
+            &StrokeFilter::default(),
+        );
+
+        let (_, stroke, ..) = document.strokes().nth(1).unwrap();
+        match stroke.style() {
+            Some(Style::Textured(options)) => {
+                assert_eq!(options.stroke_width, 3.0);
+                assert_eq!(options.stroke_color, Some(Color::BLUE));
+                assert_eq!(options.seed, Some(42));
+            }
+            style => panic!("unexpected style {style:?}"),
+        }
+    }
+}
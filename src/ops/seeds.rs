@@ -0,0 +1,118 @@
+use crate::rnotev0_5::stroke::StyleKind;
+use crate::rnotev0_5::RnotefileWrapper;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Assigns stable seeds to the rough and textured strokes which have none, so that Rnote generates the same randomness
+/// at every load and on every device.
+///
+/// The seed is the FNV-1a hash of the slot index and the serialized stroke, so it does not change between runs or
+/// platforms. Returns the number of strokes which got a seed.
+pub fn assign_stable_seeds(document: &mut RnotefileWrapper) -> anyhow::Result<usize> {
+    let store = document.store_snapshot_mut();
+    let seedless = store
+        .strokes()
+        .filter(|(_, stroke, _, _, _)| {
+            stroke
+                .style()
+                .is_some_and(|style| style.seed().is_none() && style.kind() != StyleKind::Smooth)
+        })
+        .map(|(key, stroke, _, _, _)| {
+            let content = serde_json::to_vec(stroke)?;
+            Ok((
+                key.index,
+                fnv1a(&[&(key.index as u64).to_le_bytes(), &content]),
+            ))
+        })
+        .collect::<anyhow::Result<Vec<(usize, u64)>>>()?;
+
+    for &(index, seed) in seedless.iter() {
+        if let Some(style) = store
+            .stroke_mut(index)
+            .and_then(|stroke| stroke.style_mut())
+        {
+            style.set_seed(Some(seed));
+        }
+    }
+
+    Ok(seedless.len())
+}
+
+/// The 64 bit FNV-1a hash of the concatenated data
+fn fnv1a(data: &[&[u8]]) -> u64 {
+    data.iter()
+        .flat_map(|bytes| bytes.iter())
+        .fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rnotev0_4::Background;
+    use crate::rnotev0_5::stroke::style::roughoptions::RoughOptions;
+    use crate::rnotev0_5::stroke::Style;
+    use crate::rnotev0_5::tests::{line_stroke, rectangle_stroke};
+    use crate::rnotev0_5::{DocumentBuilder, Format, Layout};
+
+    /// A document with a smooth line, two identical seedless rough rectangles and a rough rectangle with a seed
+    fn document() -> RnotefileWrapper {
+        let mut builder =
+            DocumentBuilder::new(Format::default(), Layout::Infinite, Background::default());
+        builder.add_stroke(line_stroke(na::vector![0.0, 0.0], na::vector![10.0, 0.0]));
+        for seed in [None, None, Some(42)] {
+            let mut rectangle = rectangle_stroke(na::point![0.0, 10.0], na::point![10.0, 20.0]);
+            if let Some(style) = rectangle.style_mut() {
+                *style = Style::Rough(RoughOptions {
+                    seed,
+                    ..RoughOptions::default()
+                });
+            }
+            builder.add_stroke(rectangle);
+        }
+        builder.build()
+    }
+
+    fn seeds(document: &RnotefileWrapper) -> Vec<Option<u64>> {
+        document
+            .strokes()
+            .map(|(_, stroke, ..)| stroke.style().and_then(|style| style.seed()))
+            .collect()
+    }
+
+    #[test]
+    fn only_seedless_rough_and_textured_strokes_get_seeds() {
+        let mut document = document();
+
+        assert_eq!(assign_stable_seeds(&mut document).unwrap(), 2);
+
+        let seeds = seeds(&document);
+        assert_eq!(seeds[0], None);
+        assert!(seeds[1].is_some() && seeds[2].is_some());
+        // identical strokes in different slots get different seeds
+        assert_ne!(seeds[1], seeds[2]);
+        assert_eq!(seeds[3], Some(42));
+
+        assert_eq!(assign_stable_seeds(&mut document).unwrap(), 0);
+    }
+
+    #[test]
+    fn seeds_are_deterministic() {
+        let mut first = document();
+        let mut second = document();
+
+        assign_stable_seeds(&mut first).unwrap();
+        assign_stable_seeds(&mut second).unwrap();
+
+        assert_eq!(seeds(&first), seeds(&second));
+    }
+
+    #[test]
+    fn fnv1a_matches_the_reference() {
+        assert_eq!(fnv1a(&[]), FNV_OFFSET_BASIS);
+        assert_eq!(fnv1a(&[b"a"]), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(&[b"foo", b"bar"]), fnv1a(&[b"foobar"]));
+    }
+}
@@ -0,0 +1,234 @@
+use serde::Serialize;
+
+use crate::rnotev0_5::penpath::{Element, PenPath, Segment};
+use crate::rnotev0_5::stroke::{Stroke, Style};
+use crate::rnotev0_5::RnotefileWrapper;
+
+/// The result of simplifying the brush strokes of a document
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct SimplifyReport {
+    /// The number of simplified brush strokes
+    pub strokes: usize,
+    /// The number of elements before simplifying
+    pub elements_before: usize,
+    /// The number of elements after simplifying
+    pub elements_after: usize,
+}
+
+/// Simplifies the pen paths of all brush strokes with the Ramer-Douglas-Peucker algorithm.
+///
+/// Runs of connected line segments are reduced to the elements which deviate more than the tolerance (in document units)
+/// from the simplified path. Unless `ignore_pressure` is set, the deviation includes the change of the stroke outline
+/// caused by the difference to the interpolated pressure. Curve segments and dots are kept unchanged.
+pub fn simplify(
+    document: &mut RnotefileWrapper,
+    tolerance: f64,
+    ignore_pressure: bool,
+) -> SimplifyReport {
+    let mut report = SimplifyReport::default();
+
+    for stroke in document.store_snapshot_mut().strokes_mut() {
+        let brushstroke = match stroke {
+            Stroke::BrushStroke(brushstroke) => brushstroke,
+            _ => continue,
+        };
+        // the outline deviates by half of the width change caused by the pressure
+        let pressure_weight = match &brushstroke.style {
+            _ if ignore_pressure => 0.0,
+            Style::Smooth(options) if options.segment_constant_width => 0.0,
+            style => style.stroke_width() * 0.5,
+        };
+
+        report.strokes += 1;
+        report.elements_before += count_elements(&brushstroke.path);
+        brushstroke.path = simplify_penpath(&brushstroke.path, tolerance, pressure_weight);
+        report.elements_after += count_elements(&brushstroke.path);
+    }
+
+    report
+}
+
+/// Counts the elements of the path. Elements shared by consecutive segments are counted once.
+pub fn count_elements(path: &PenPath) -> usize {
+    let mut prev_end: Option<na::Vector2<f64>> = None;
+
+    path.iter()
+        .map(|segment| {
+            let count = match segment {
+                Segment::Dot { .. } => 1,
+                _ if prev_end == Some(segment.start().pos) => 1,
+                _ => 2,
+            };
+            prev_end = Some(segment.end().pos);
+            count
+        })
+        .sum()
+}
+
+fn simplify_penpath(path: &PenPath, tolerance: f64, pressure_weight: f64) -> PenPath {
+    let mut segments = Vec::with_capacity(path.len());
+    let mut run: Vec<Element> = vec![];
+
+    let flush_run = |run: &mut Vec<Element>, segments: &mut Vec<Segment>| {
+        let kept = rdp(run, tolerance, pressure_weight);
+        segments.extend(kept.windows(2).map(|pair| Segment::Line {
+            start: pair[0],
+            end: pair[1],
+        }));
+        run.clear();
+    };
+
+    for segment in path.iter() {
+        match segment {
+            Segment::Line { start, end } => {
+                if run.last().map(|last| last.pos) != Some(start.pos) {
+                    flush_run(&mut run, &mut segments);
+                    run.push(*start);
+                }
+                run.push(*end);
+            }
+            _ => {
+                flush_run(&mut run, &mut segments);
+                segments.push(segment.clone());
+            }
+        }
+    }
+    flush_run(&mut run, &mut segments);
+
+    PenPath(segments.into())
+}
+
+/// Returns the elements kept by the Ramer-Douglas-Peucker algorithm. The first and last element are always kept.
+fn rdp(elements: &[Element], tolerance: f64, pressure_weight: f64) -> Vec<Element> {
+    if elements.len() <= 2 {
+        return elements.to_vec();
+    }
+    let mut keep = vec![false; elements.len()];
+    keep[0] = true;
+    keep[elements.len() - 1] = true;
+
+    let mut ranges = vec![(0, elements.len() - 1)];
+    while let Some((first, last)) = ranges.pop() {
+        let (start, end) = (elements[first], elements[last]);
+        let farthest = (first + 1..last)
+            .map(|i| (i, deviation(&elements[i], &start, &end, pressure_weight)))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b));
+
+        if let Some((i, deviation)) = farthest {
+            if deviation > tolerance {
+                keep[i] = true;
+                ranges.push((first, i));
+                ranges.push((i, last));
+            }
+        }
+    }
+
+    elements
+        .iter()
+        .zip(keep)
+        .filter_map(|(element, keep)| keep.then_some(*element))
+        .collect()
+}
+
+/// The deviation of the element from the line between start and end, including the weighted deviation of its pressure
+/// from the pressure interpolated along the line
+fn deviation(element: &Element, start: &Element, end: &Element, pressure_weight: f64) -> f64 {
+    let line = end.pos - start.pos;
+    let t = if line.magnitude_squared() > 0.0 {
+        ((element.pos - start.pos).dot(&line) / line.magnitude_squared()).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let distance = (element.pos - (start.pos + line * t)).magnitude();
+    let pressure = start.pressure + (end.pressure - start.pressure) * t;
+
+    distance + (element.pressure - pressure).abs() * pressure_weight
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A path of line segments through the elements given as `(x, y, pressure)`
+    fn polyline(elements: &[(f64, f64, f64)]) -> PenPath {
+        let elements: Vec<Element> = elements
+            .iter()
+            .map(|&(x, y, pressure)| Element::new(na::vector![x, y], pressure))
+            .collect();
+
+        PenPath(
+            elements
+                .windows(2)
+                .map(|pair| Segment::Line {
+                    start: pair[0],
+                    end: pair[1],
+                })
+                .collect(),
+        )
+    }
+
+    fn positions(path: &PenPath) -> Vec<na::Vector2<f64>> {
+        let mut positions: Vec<na::Vector2<f64>> =
+            path.iter().map(|segment| segment.start().pos).collect();
+        positions.extend(path.back().map(|segment| segment.end().pos));
+        positions
+    }
+
+    #[test]
+    fn elements_within_the_tolerance_are_removed() {
+        let path = polyline(&[
+            (0.0, 0.0, 0.5),
+            (5.0, 0.1, 0.5),
+            (10.0, 0.0, 0.5),
+            (10.0, 10.0, 0.5),
+        ]);
+        assert_eq!(count_elements(&path), 4);
+
+        let simplified = simplify_penpath(&path, 0.5, 0.0);
+
+        // the corner deviates more than the tolerance and is kept
+        assert_eq!(count_elements(&simplified), 3);
+        assert_eq!(
+            positions(&simplified),
+            vec![
+                na::vector![0.0, 0.0],
+                na::vector![10.0, 0.0],
+                na::vector![10.0, 10.0]
+            ]
+        );
+    }
+
+    #[test]
+    fn pressure_changes_are_kept() {
+        let path = polyline(&[(0.0, 0.0, 0.2), (5.0, 0.0, 1.0), (10.0, 0.0, 0.2)]);
+
+        assert_eq!(count_elements(&simplify_penpath(&path, 1.0, 0.0)), 2);
+        // with a width of 4.0 the outline changes by 0.8 * 2.0
+        assert_eq!(count_elements(&simplify_penpath(&path, 1.0, 2.0)), 3);
+    }
+
+    #[test]
+    fn curves_and_disconnected_runs_are_kept() {
+        let mut path = polyline(&[(0.0, 0.0, 0.5), (5.0, 0.0, 0.5), (10.0, 0.0, 0.5)]);
+        path.push_back(Segment::QuadBez {
+            start: Element::new(na::vector![10.0, 0.0], 0.5),
+            cp: na::vector![15.0, 5.0],
+            end: Element::new(na::vector![20.0, 0.0], 0.5),
+        });
+        path.extend(polyline(&[(30.0, 0.0, 0.5), (35.0, 0.0, 0.5), (40.0, 0.0, 0.5)]).0);
+
+        let simplified = simplify_penpath(&path, 0.5, 0.0);
+
+        assert_eq!(simplified.len(), 3);
+        assert!(matches!(simplified[1], Segment::QuadBez { .. }));
+        assert_eq!(
+            positions(&simplified),
+            vec![
+                na::vector![0.0, 0.0],
+                na::vector![10.0, 0.0],
+                na::vector![30.0, 0.0],
+                na::vector![40.0, 0.0]
+            ]
+        );
+    }
+}
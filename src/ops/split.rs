@@ -0,0 +1,218 @@
+use anyhow::anyhow;
+use clap::ArgEnum;
+use p2d::bounding_volume::{BoundingVolume, AABB};
+
+use crate::ops::format::validate_format;
+use crate::rnotev0_5::{Document, Layout, RnotefileWrapper, ShapeBehaviour, TransformBehaviour};
+
+/// How strokes which straddle the boundary between pages are assigned
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StraddlePolicy {
+    /// The stroke is duplicated onto every page it intersects
+    Duplicate,
+    /// The stroke is assigned to the page containing the center of its bounds
+    Center,
+    /// The stroke is kept on the first page it intersects
+    First,
+}
+
+/// Splits a document with a page based layout into one document per page.
+///
+/// The strokes of every page are translated so that the page starts at the origin.
+/// Documents with a format outside of the limits of `Format` or with invalid extents are rejected.
+pub fn split(
+    document: &RnotefileWrapper,
+    policy: StraddlePolicy,
+) -> anyhow::Result<Vec<RnotefileWrapper>> {
+    let doc = document.document();
+    if doc.layout() == Layout::Infinite {
+        return Err(anyhow!(
+            "splitting requires a fixed size or continuous vertical layout"
+        ));
+    }
+    validate_format(&doc.format)?;
+    if !(doc.width.is_finite() && doc.width >= 0.0 && doc.height.is_finite() && doc.height >= 0.0) {
+        return Err(anyhow!(
+            "the document extents {} x {} are invalid",
+            doc.width,
+            doc.height
+        ));
+    }
+    let pages = pages_bounds(doc);
+    let store = document.store_snapshot();
+
+    let mut page_strokes = vec![vec![]; pages.len()];
+    for (index, slot) in store.stroke_components().iter().enumerate() {
+        let stroke = match &slot.value {
+            Some(stroke) if !store.is_trashed(index) => stroke,
+            _ => continue,
+        };
+        let bounds = stroke.bounds();
+        let mut intersecting = pages
+            .iter()
+            .enumerate()
+            .filter(|(_, page)| page.intersects(&bounds))
+            .map(|(i, _)| i);
+        let center_page = page_containing(doc, &pages, bounds.center());
+
+        match policy {
+            StraddlePolicy::Duplicate => {
+                let intersecting = intersecting.collect::<Vec<usize>>();
+                if intersecting.is_empty() {
+                    page_strokes[center_page].push(index);
+                }
+                for page in intersecting {
+                    page_strokes[page].push(index);
+                }
+            }
+            StraddlePolicy::Center => page_strokes[center_page].push(index),
+            StraddlePolicy::First => {
+                page_strokes[intersecting.next().unwrap_or(center_page)].push(index)
+            }
+        }
+    }
+
+    Ok(pages
+        .iter()
+        .zip(page_strokes)
+        .map(|(page, indices)| {
+            let mut page_store = store.clone_slots(&indices);
+            page_store
+                .strokes_mut()
+                .for_each(|stroke| stroke.translate(-page.mins.coords));
+
+            let mut page_document = doc.clone();
+            page_document.x = 0.0;
+            page_document.y = 0.0;
+            page_document.width = page.extents()[0];
+            page_document.height = page.extents()[1];
+            page_document.set_layout(Layout::FixedSize);
+
+            RnotefileWrapper::new(page_document, page_store)
+        })
+        .collect())
+}
+
+/// The bounds of the pages of the document, ordered from left to right, top to bottom.
+pub fn pages_bounds(doc: &Document) -> Vec<AABB> {
+    let (n_columns, n_rows) = pages_grid(doc);
+
+    (0..n_rows)
+        .flat_map(|row| {
+            (0..n_columns).map(move |column| {
+                let mins = na::point![
+                    doc.x + f64::from(column) * doc.format.width,
+                    doc.y + f64::from(row) * doc.format.height
+                ];
+                AABB::new(
+                    mins,
+                    mins + na::vector![doc.format.width, doc.format.height],
+                )
+            })
+        })
+        .collect()
+}
+
+/// The number of page columns and rows of the document
+fn pages_grid(doc: &Document) -> (u32, u32) {
+    let n_columns = (doc.width / doc.format.width).ceil().max(1.0) as u32;
+    let n_rows = (doc.height / doc.format.height).ceil().max(1.0) as u32;
+
+    (n_columns, n_rows)
+}
+
+/// The index of the page containing the point, or the nearest page if it is outside of the document
+fn page_containing(doc: &Document, pages: &[AABB], point: na::Point2<f64>) -> usize {
+    let (n_columns, n_rows) = pages_grid(doc);
+    let column = ((point[0] - doc.x) / doc.format.width)
+        .floor()
+        .clamp(0.0, f64::from(n_columns - 1)) as usize;
+    let row = ((point[1] - doc.y) / doc.format.height)
+        .floor()
+        .clamp(0.0, f64::from(n_rows - 1)) as usize;
+
+    (row * n_columns as usize + column).min(pages.len() - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rnotev0_4::Background;
+    use crate::rnotev0_5::tests::{line_stroke, roundtrip};
+    use crate::rnotev0_5::{DocumentBuilder, Format};
+
+    /// A fixed size document with two pages, a stroke on each and one which straddles both
+    fn two_pages() -> RnotefileWrapper {
+        let mut builder =
+            DocumentBuilder::new(Format::default(), Layout::FixedSize, Background::default());
+        let page_height = Format::default().height;
+        builder.add_stroke(line_stroke(
+            na::vector![100.0, 100.0],
+            na::vector![200.0, 100.0],
+        ));
+        builder.add_stroke(line_stroke(
+            na::vector![100.0, page_height + 100.0],
+            na::vector![200.0, page_height + 100.0],
+        ));
+        builder.add_stroke(line_stroke(
+            na::vector![300.0, page_height - 10.0],
+            na::vector![300.0, page_height + 50.0],
+        ));
+        builder.build()
+    }
+
+    fn strokes_per_page(pages: &[RnotefileWrapper]) -> Vec<usize> {
+        pages.iter().map(|page| page.strokes().count()).collect()
+    }
+
+    #[test]
+    fn split_pages_can_be_loaded() {
+        let pages = split(&two_pages(), StraddlePolicy::Duplicate).unwrap();
+
+        assert_eq!(strokes_per_page(&pages), vec![2, 2]);
+        for page in pages.iter() {
+            let loaded = roundtrip(page);
+            assert_eq!(loaded.document().layout(), Layout::FixedSize);
+            assert_eq!(loaded.document().height, Format::default().height);
+        }
+        // the strokes of the second page are moved to the origin
+        let (_, stroke, _, _, _) = pages[1].strokes().next().unwrap();
+        assert!((stroke.bounds().center()[1] - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn straddling_strokes_follow_the_policy() {
+        let document = two_pages();
+
+        assert_eq!(
+            strokes_per_page(&split(&document, StraddlePolicy::Center).unwrap()),
+            vec![1, 2]
+        );
+        assert_eq!(
+            strokes_per_page(&split(&document, StraddlePolicy::First).unwrap()),
+            vec![2, 1]
+        );
+    }
+
+    #[test]
+    fn infinite_layout_is_rejected() {
+        let mut document = two_pages();
+        document.document_mut().set_layout(Layout::Infinite);
+
+        assert!(split(&document, StraddlePolicy::Center).is_err());
+    }
+
+    #[test]
+    fn invalid_sizes_are_rejected() {
+        for width in [0.0, -10.0, f64::NAN] {
+            let mut document = two_pages();
+            document.document_mut().format.width = width;
+
+            assert!(split(&document, StraddlePolicy::Center).is_err());
+        }
+
+        let mut document = two_pages();
+        document.document_mut().height = f64::INFINITY;
+        assert!(split(&document, StraddlePolicy::Center).is_err());
+    }
+}
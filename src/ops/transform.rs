@@ -0,0 +1,300 @@
+use anyhow::anyhow;
+use clap::ArgEnum;
+use p2d::bounding_volume::BoundingVolume;
+use serde::Serialize;
+
+use crate::rnotev0_5::store::{StoreSnapshot, StrokeFilter};
+use crate::rnotev0_5::stroke::Stroke;
+use crate::rnotev0_5::{RnotefileWrapper, ShapeBehaviour, TransformBehaviour};
+
+/// The axis strokes are mirrored along
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum MirrorAxis {
+    /// Flips left and right
+    Horizontal,
+    /// Flips top and bottom
+    Vertical,
+}
+
+/// A geometric transformation. Transformations with a center use the center of the bounds of the selected strokes
+/// when it is not set.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Transformation {
+    Translate(na::Vector2<f64>),
+    Scale {
+        factors: na::Vector2<f64>,
+        center: Option<na::Point2<f64>>,
+    },
+    /// Rotates by the angle in radians. Positive angles rotate clockwise, as the y axis points down.
+    Rotate {
+        angle: f64,
+        center: Option<na::Point2<f64>>,
+    },
+    Mirror {
+        axis: MirrorAxis,
+        center: Option<na::Point2<f64>>,
+    },
+}
+
+impl Transformation {
+    /// Rejects non-finite values and scale factors of zero, which would collapse the strokes
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let finite = |v: na::Vector2<f64>| v.iter().all(|c| c.is_finite());
+        match *self {
+            Self::Translate(offset) if !finite(offset) => Err(anyhow!(
+                "the offset {}, {} is not finite",
+                offset[0],
+                offset[1]
+            )),
+            Self::Scale { factors, .. }
+                if !finite(factors) || factors.iter().any(|f| *f == 0.0) =>
+            {
+                Err(anyhow!(
+                    "the scale factors {}, {} must be finite and not zero",
+                    factors[0],
+                    factors[1]
+                ))
+            }
+            Self::Rotate { angle, .. } if !angle.is_finite() => {
+                Err(anyhow!("the angle {angle} is not finite"))
+            }
+            _ => match self.center() {
+                Some(center) if !finite(center.coords) => Err(anyhow!(
+                    "the center {}, {} is not finite",
+                    center[0],
+                    center[1]
+                )),
+                _ => Ok(()),
+            },
+        }
+    }
+
+    /// Applies the transformation to the stroke, around the resolved center
+    fn apply(&self, stroke: &mut Stroke, center: na::Point2<f64>, scale_stroke_widths: bool) {
+        match *self {
+            Self::Translate(offset) => stroke.translate(offset),
+            Self::Scale { factors, .. } => {
+                scale_around(stroke, factors, center);
+                if scale_stroke_widths {
+                    if let Some(style) = stroke.style_mut() {
+                        // the geometric mean keeps the area of the stroke outline proportional for non-uniform factors
+                        let factor = (factors[0] * factors[1]).abs().sqrt();
+                        style.set_stroke_width(style.stroke_width() * factor);
+                    }
+                }
+            }
+            Self::Rotate { angle, .. } => stroke.rotate(angle, center),
+            Self::Mirror { axis, .. } => {
+                let factors = match axis {
+                    MirrorAxis::Horizontal => na::vector![-1.0, 1.0],
+                    MirrorAxis::Vertical => na::vector![1.0, -1.0],
+                };
+                scale_around(stroke, factors, center);
+            }
+        }
+    }
+
+    fn center(&self) -> Option<na::Point2<f64>> {
+        match *self {
+            Self::Translate(_) => None,
+            Self::Scale { center, .. }
+            | Self::Rotate { center, .. }
+            | Self::Mirror { center, .. } => center,
+        }
+    }
+}
+
+fn scale_around(stroke: &mut Stroke, factors: na::Vector2<f64>, center: na::Point2<f64>) {
+    stroke.translate(-center.coords);
+    stroke.scale(factors);
+    stroke.translate(center.coords);
+}
+
+/// Applies the transformations in order to the strokes which match the filter, including trashed strokes.
+/// The strokes are selected once, before they are transformed.
+/// Transformations without a center use the center of the bounds of the selected strokes, recomputed before each
+/// transformation so that it follows the strokes moved by the previous ones.
+/// Stroke widths are scaled with the scale factors when `scale_stroke_widths` is set.
+///
+/// Returns the number of transformed strokes, or an error if a transformation is invalid (see
+/// `Transformation::validate()`), in which case nothing is transformed.
+pub fn transform(
+    document: &mut RnotefileWrapper,
+    transformations: &[Transformation],
+    filter: &StrokeFilter,
+    scale_stroke_widths: bool,
+) -> anyhow::Result<usize> {
+    for transformation in transformations {
+        transformation.validate()?;
+    }
+
+    let store = document.store_snapshot_mut();
+    let indices: Vec<usize> = store
+        .strokes_filtered(filter)
+        .map(|(key, _, _, _, _)| key.index)
+        .collect();
+
+    for transformation in transformations {
+        // the previous transformations may have moved the selection, so its center is recomputed
+        let center = transformation
+            .center()
+            .unwrap_or_else(|| selection_center(store, &indices));
+        for &index in indices.iter() {
+            if let Some(stroke) = store.stroke_mut(index) {
+                transformation.apply(stroke, center, scale_stroke_widths);
+            }
+        }
+    }
+
+    Ok(indices.len())
+}
+
+/// The center of the bounds of the strokes at the slot indices, or the origin if there are none
+fn selection_center(store: &StoreSnapshot, indices: &[usize]) -> na::Point2<f64> {
+    indices
+        .iter()
+        .filter_map(|&index| store.stroke_components().get(index)?.value.as_ref())
+        .map(|stroke| stroke.bounds())
+        .reduce(|acc, bounds| acc.merged(&bounds))
+        .map_or_else(na::Point2::origin, |bounds| bounds.center())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rnotev0_4::Background;
+    use crate::rnotev0_5::stroke::StrokeType;
+    use crate::rnotev0_5::tests::{line_stroke, rectangle_stroke};
+    use crate::rnotev0_5::{DocumentBuilder, Format, Layout};
+
+    /// A document with a line from (0, 0) to (10, 0) and a rectangle from (100, 100) to (200, 200)
+    fn document() -> RnotefileWrapper {
+        let mut builder =
+            DocumentBuilder::new(Format::default(), Layout::Infinite, Background::default());
+        builder.add_stroke(line_stroke(na::vector![0.0, 0.0], na::vector![10.0, 0.0]));
+        builder.add_stroke(rectangle_stroke(
+            na::point![100.0, 100.0],
+            na::point![200.0, 200.0],
+        ));
+        builder.build()
+    }
+
+    fn centers(document: &RnotefileWrapper) -> Vec<na::Point2<f64>> {
+        document
+            .strokes()
+            .map(|(_, stroke, ..)| stroke.bounds().center())
+            .collect()
+    }
+
+    fn assert_near(a: na::Point2<f64>, b: na::Point2<f64>) {
+        assert!((a - b).magnitude() < 1e-9, "{a} is not near {b}");
+    }
+
+    #[test]
+    fn filtered_strokes_are_transformed_around_their_center() {
+        let mut document = document();
+        let filter = StrokeFilter::default().variant(StrokeType::ShapeStroke);
+        let transformations = [
+            Transformation::Mirror {
+                axis: MirrorAxis::Horizontal,
+                center: None,
+            },
+            Transformation::Scale {
+                factors: na::vector![2.0, 2.0],
+                center: None,
+            },
+        ];
+
+        assert_eq!(
+            transform(&mut document, &transformations, &filter, true).unwrap(),
+            1
+        );
+
+        let (_, rectangle, ..) = document.strokes().nth(1).unwrap();
+        assert_near(rectangle.bounds().center(), na::point![150.0, 150.0]);
+        assert_eq!(rectangle.style().unwrap().stroke_width(), 2.0);
+        assert_eq!(rectangle.bounds().extents(), na::vector![202.0, 202.0]);
+        assert_near(centers(&document)[0], na::point![5.0, 0.0]);
+    }
+
+    #[test]
+    fn center_follows_the_translated_selection() {
+        let mut document = document();
+        let filter = StrokeFilter::default().variant(StrokeType::BrushStroke);
+        let transformations = [
+            Transformation::Translate(na::vector![100.0, 0.0]),
+            Transformation::Rotate {
+                angle: std::f64::consts::PI,
+                center: None,
+            },
+        ];
+
+        transform(&mut document, &transformations, &filter, false).unwrap();
+
+        // rotating around the center of the translated line keeps it in place
+        assert_near(centers(&document)[0], na::point![105.0, 0.0]);
+    }
+
+    #[test]
+    fn explicit_center_is_used() {
+        let mut document = document();
+        let transformations = [Transformation::Rotate {
+            angle: std::f64::consts::FRAC_PI_2,
+            center: Some(na::point![0.0, 0.0]),
+        }];
+
+        transform(
+            &mut document,
+            &transformations,
+            &StrokeFilter::default(),
+            false,
+        )
+        .unwrap();
+
+        // positive angles rotate clockwise
+        let centers = centers(&document);
+        assert_near(centers[0], na::point![0.0, 5.0]);
+        assert_near(centers[1], na::point![-150.0, 150.0]);
+    }
+
+    #[test]
+    fn invalid_transformations_are_rejected() {
+        let invalid = [
+            Transformation::Scale {
+                factors: na::vector![0.0, 1.0],
+                center: None,
+            },
+            Transformation::Scale {
+                factors: na::vector![f64::INFINITY, 1.0],
+                center: None,
+            },
+            Transformation::Rotate {
+                angle: f64::NAN,
+                center: None,
+            },
+            Transformation::Translate(na::vector![f64::NAN, 0.0]),
+            Transformation::Mirror {
+                axis: MirrorAxis::Vertical,
+                center: Some(na::point![f64::NAN, 0.0]),
+            },
+        ];
+
+        for transformation in invalid {
+            let mut document = document();
+            // the valid transformation before the invalid one is not applied either
+            let transformations = [
+                Transformation::Translate(na::vector![10.0, 0.0]),
+                transformation,
+            ];
+
+            assert!(transform(
+                &mut document,
+                &transformations,
+                &StrokeFilter::default(),
+                false
+            )
+            .is_err());
+            assert_near(centers(&document)[0], na::point![5.0, 0.0]);
+        }
+    }
+}
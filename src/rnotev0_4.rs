@@ -1,3 +1,6 @@
+use std::str::FromStr;
+
+use clap::ArgEnum;
 use serde::{Deserialize, Serialize};
 
 pub mod strokesstate;
@@ -58,7 +61,7 @@ impl Format {
     pub const DPI_DEFAULT: f64 = 96.0;
 }
 
-#[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[derive(ArgEnum, Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
 #[repr(u32)]
 #[serde(rename = "orientation")]
 pub enum Orientation {
@@ -76,7 +79,7 @@ impl Default for Orientation {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(default, rename = "background")]
 pub struct Background {
     #[serde(rename = "color")]
@@ -110,7 +113,7 @@ impl Default for Background {
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[derive(ArgEnum, Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
 #[repr(u32)]
 #[serde(rename = "pattern_style")]
 pub enum PatternStyle {
@@ -124,7 +127,7 @@ pub enum PatternStyle {
     Dots,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[serde(default, rename = "color")]
 pub struct Color {
     #[serde(rename = "r")]
@@ -185,4 +188,69 @@ impl Color {
         b: 1.0,
         a: 1.0,
     };
+
+    /// The lightness in the HSL color space, between 0.0 and 1.0
+    pub fn lightness(&self) -> f64 {
+        (self.r.max(self.g).max(self.b) + self.r.min(self.g).min(self.b)) * 0.5
+    }
+
+    /// The euclidean distance to the other color in the RGBA color space, between 0.0 and 2.0
+    pub fn distance(&self, other: &Self) -> f64 {
+        ((self.r - other.r).powi(2)
+            + (self.g - other.g).powi(2)
+            + (self.b - other.b).powi(2)
+            + (self.a - other.a).powi(2))
+        .sqrt()
+    }
+
+    /// The grayscale color with the relative luminance of the color (Rec. 709 coefficients), keeping its alpha
+    pub fn to_grayscale(&self) -> Self {
+        let luminance = 0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b;
+
+        Self {
+            r: luminance,
+            g: luminance,
+            b: luminance,
+            a: self.a,
+        }
+    }
+
+    /// The color with inverted lightness in the HSL color space, keeping its hue, saturation and alpha
+    pub fn with_inverted_lightness(&self) -> Self {
+        // shifting all channels equally keeps the chroma and hue, 1 - max - min maps the lightness L to 1 - L
+        let shift = 1.0 - self.r.max(self.g).max(self.b) - self.r.min(self.g).min(self.b);
+
+        Self {
+            r: (self.r + shift).clamp(0.0, 1.0),
+            g: (self.g + shift).clamp(0.0, 1.0),
+            b: (self.b + shift).clamp(0.0, 1.0),
+            a: self.a,
+        }
+    }
+}
+
+impl FromStr for Color {
+    type Err = anyhow::Error;
+
+    /// Parses a color from a hex string, `#rrggbb` or `#rrggbbaa`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.trim().trim_start_matches('#');
+        if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+            return Err(anyhow::anyhow!(
+                "expected a color in the form `#rrggbb` or `#rrggbbaa`, got '{s}'"
+            ));
+        }
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .map_or(Ok(255), |channel| u8::from_str_radix(channel, 16))
+                .map(|value| f64::from(value) / 255.0)
+        };
+
+        Ok(Self {
+            r: channel(0)?,
+            g: channel(2)?,
+            b: channel(4)?,
+            a: channel(6)?,
+        })
+    }
 }
@@ -208,7 +208,7 @@ impl RoughOptions {
 }
 
 /// available Fill styles
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum FillStyle {
     /// Solid
     Solid,
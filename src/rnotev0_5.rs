@@ -1,15 +1,23 @@
+use clap::ArgEnum;
+use p2d::bounding_volume::AABB;
 use serde::{Deserialize, Serialize};
 
 use crate::rnotev0_4::Background;
 pub use crate::rnotev0_4::{Color, Orientation};
 
+pub mod builder;
 pub mod penpath;
+pub mod shapebehaviour;
 pub mod shapes;
 pub mod store;
 pub mod stroke;
 pub mod transform;
+pub mod transformbehaviour;
 
+pub use builder::DocumentBuilder;
+pub use shapebehaviour::ShapeBehaviour;
 pub use transform::Transform;
+pub use transformbehaviour::TransformBehaviour;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename = "rnotefile_wrapper")]
@@ -22,6 +30,72 @@ pub struct RnotefileWrapper {
 
 impl RnotefileWrapper {
     pub const VERSION: &'static str = "0.5.1";
+
+    /// A new file from the document and a store snapshot
+    pub fn new(document: Document, store_snapshot: store::StoreSnapshot) -> Self {
+        Self {
+            version: Self::VERSION.to_owned(),
+            data: RnotefileMaj0Min5 {
+                document,
+                store_snapshot,
+            },
+        }
+    }
+
+    /// Splits the file into its document and store snapshot
+    pub fn into_parts(self) -> (Document, store::StoreSnapshot) {
+        (self.data.document, self.data.store_snapshot)
+    }
+
+    /// The document
+    pub fn document(&self) -> &Document {
+        &self.data.document
+    }
+
+    /// The document, mutable
+    pub fn document_mut(&mut self) -> &mut Document {
+        &mut self.data.document
+    }
+
+    /// The store snapshot holding the strokes
+    pub fn store_snapshot(&self) -> &store::StoreSnapshot {
+        &self.data.store_snapshot
+    }
+
+    /// The store snapshot holding the strokes, mutable
+    pub fn store_snapshot_mut(&mut self) -> &mut store::StoreSnapshot {
+        &mut self.data.store_snapshot
+    }
+
+    /// Iterates over the existing strokes, see `StoreSnapshot::strokes()`
+    pub fn strokes(&self) -> impl Iterator<Item = store::StrokeEntry<'_>> {
+        self.data.store_snapshot.strokes()
+    }
+
+    /// Fits the document extents to the strokes which are not trashed, see `Document::fit_extents()`.
+    /// Page based layouts start at the origin, so strokes at negative coordinates are first moved onto the pages,
+    /// see `Document::content_offset()`. Returns the offset the strokes were moved by.
+    pub fn fit_extents(&mut self) -> na::Vector2<f64> {
+        let offset = self
+            .data
+            .document
+            .content_offset(self.data.store_snapshot.strokes_bounds());
+        if offset != na::Vector2::zeros() {
+            for stroke in self.data.store_snapshot.strokes_mut() {
+                stroke.translate(offset);
+            }
+        }
+
+        let content = self.data.store_snapshot.strokes_bounds();
+        self.data.document.fit_extents(content);
+
+        offset
+    }
+
+    /// Asserts that the file can be loaded by Rnote, see `StoreSnapshot::assert_valid()`
+    pub fn assert_valid(&self) -> anyhow::Result<()> {
+        self.data.store_snapshot.assert_valid()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,7 +109,7 @@ pub struct RnotefileMaj0Min5 {
     pub store_snapshot: store::StoreSnapshot,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(default, rename = "document")]
 pub struct Document {
     #[serde(rename = "x")]
@@ -68,7 +142,7 @@ impl Default for Document {
     }
 }
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(default, rename = "format")]
 pub struct Format {
     #[serde(rename = "width")]
@@ -110,6 +184,86 @@ impl From<crate::rnotev0_4::Format> for Format {
     }
 }
 
+impl Document {
+    /// The distance content may extend beyond the origin and still be considered to be on the pages
+    pub const PAGE_BOUNDARY_TOLERANCE: f64 = 2.0;
+
+    /// The layout of the document
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// Sets the layout of the document
+    pub fn set_layout(&mut self, layout: Layout) {
+        self.layout = layout;
+    }
+
+    /// Fits the extents to the bounds of the content, depending on the layout.
+    /// For page based layouts the document starts at the origin and the extents are multiples of the format size
+    /// (only vertically for the continuous vertical layout). The infinite layout covers the content and at least one page.
+    /// Page based layouts do not cover content at negative coordinates, it has to be moved by `content_offset()` first.
+    pub fn fit_extents(&mut self, content: Option<AABB>) {
+        let pages =
+            |extent: f64, page_extent: f64| (extent / page_extent).ceil().max(1.0) * page_extent;
+        let maxs = content.map_or(na::point![0.0, 0.0], |bounds| bounds.maxs);
+
+        match self.layout {
+            Layout::FixedSize => {
+                self.x = 0.0;
+                self.y = 0.0;
+                self.width = pages(maxs[0], self.format.width);
+                self.height = pages(maxs[1], self.format.height);
+            }
+            Layout::ContinuousVertical => {
+                self.x = 0.0;
+                self.y = 0.0;
+                self.width = self.format.width;
+                self.height = pages(maxs[1], self.format.height);
+            }
+            Layout::Infinite => {
+                let mins = content.map_or(na::point![0.0, 0.0], |bounds| bounds.mins);
+                self.x = mins[0].min(0.0);
+                self.y = mins[1].min(0.0);
+                self.width = (maxs[0] - self.x).max(self.format.width);
+                self.height = (maxs[1] - self.y).max(self.format.height);
+            }
+        }
+    }
+
+    /// The offset which moves the content onto the pages of page based layouts, zero for the infinite layout.
+    /// Along the axes with page boundaries the offset is a multiple of the format size, so the content keeps its
+    /// position on the pages. Content extending less than `PAGE_BOUNDARY_TOLERANCE` beyond the origin is not moved.
+    pub fn content_offset(&self, content: Option<AABB>) -> na::Vector2<f64> {
+        let mins = match (self.layout, content) {
+            (Layout::Infinite, _) | (_, None) => return na::Vector2::zeros(),
+            (_, Some(content)) => content.mins,
+        };
+        let outside = |min: f64| min < -Self::PAGE_BOUNDARY_TOLERANCE;
+        let whole_pages = |min: f64, page_extent: f64| {
+            if outside(min) {
+                (-min / page_extent).ceil() * page_extent
+            } else {
+                0.0
+            }
+        };
+
+        let x = match self.layout {
+            Layout::FixedSize => whole_pages(mins[0], self.format.width),
+            _ if outside(mins[0]) => -mins[0],
+            _ => 0.0,
+        };
+        na::vector![x, whole_pages(mins[1], self.format.height)]
+    }
+
+    /// The bounds of the document extents
+    pub fn bounds(&self) -> AABB {
+        AABB::new(
+            na::point![self.x, self.y],
+            na::point![self.x + self.width, self.y + self.height],
+        )
+    }
+}
+
 impl Format {
     pub const WIDTH_MIN: f64 = 1.0;
     pub const WIDTH_MAX: f64 = 30000.0;
@@ -124,7 +278,7 @@ impl Format {
     pub const DPI_DEFAULT: f64 = 96.0;
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[serde(rename = "layout")]
 pub enum Layout {
     #[serde(rename = "fixed_size")]
@@ -182,7 +336,7 @@ impl From<crate::rnotev0_4::Sheet> for RnotefileWrapper {
 
         let data = RnotefileMaj0Min5 {
             document,
-            store_snapshot: strokes_state.into()
+            store_snapshot: strokes_state.into(),
         };
         Self {
             version: Self::VERSION.to_owned(),
@@ -190,3 +344,43 @@ impl From<crate::rnotev0_4::Sheet> for RnotefileWrapper {
         }
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::penpath::{Element, Segment};
+    use super::shapes::{Rectangle, Shape};
+    use super::stroke::{BrushStroke, ShapeStroke, Stroke, Style};
+    use super::*;
+
+    /// Writes the document as a compressed v0.5 file and loads it again, asserting that Rnote can load it
+    pub(crate) fn roundtrip(document: &RnotefileWrapper) -> RnotefileWrapper {
+        let bytes = crate::RnoteDocument::V0_5(document.clone())
+            .into_output(crate::OutputType::V0_5, "test.rnote")
+            .unwrap();
+        let loaded = crate::RnoteDocument::from_bytes_detect(bytes)
+            .unwrap()
+            .into_v0_5();
+
+        loaded.assert_valid().unwrap();
+        loaded
+    }
+
+    /// A smooth brush stroke with a single line segment
+    pub(crate) fn line_stroke(start: na::Vector2<f64>, end: na::Vector2<f64>) -> Stroke {
+        Stroke::BrushStroke(BrushStroke::new(
+            Segment::Line {
+                start: Element::new(start, 0.5),
+                end: Element::new(end, 0.5),
+            },
+            Style::default(),
+        ))
+    }
+
+    /// A smooth rectangle shape stroke
+    pub(crate) fn rectangle_stroke(mins: na::Point2<f64>, maxs: na::Point2<f64>) -> Stroke {
+        Stroke::ShapeStroke(ShapeStroke::new(
+            Shape::Rectangle(Rectangle::from_p2d_aabb(AABB::new(mins, maxs))),
+            Style::default(),
+        ))
+    }
+}
@@ -0,0 +1,112 @@
+use crate::rnotev0_4::Background;
+use crate::rnotev0_5::penpath::PenPath;
+use crate::rnotev0_5::shapes::Shape;
+use crate::rnotev0_5::store::StoreSnapshot;
+use crate::rnotev0_5::stroke::{BrushStroke, ShapeStroke, Stroke, Style};
+use crate::rnotev0_5::{Document, Format, Layout, RnotefileWrapper};
+
+/// Builds a v0.5 document from scratch.
+///
+/// Strokes are inserted with fresh components and are ordered in the order they are added.
+/// The document extents are fitted to the format and the added strokes when the document is built.
+#[derive(Debug, Clone, Default)]
+pub struct DocumentBuilder {
+    document: Document,
+    store: StoreSnapshot,
+}
+
+impl DocumentBuilder {
+    pub fn new(format: Format, layout: Layout, background: Background) -> Self {
+        let mut document = Document {
+            width: format.width,
+            height: format.height,
+            format,
+            background,
+            ..Document::default()
+        };
+        document.set_layout(layout);
+
+        Self {
+            document,
+            store: StoreSnapshot::default(),
+        }
+    }
+
+    /// Adds a brush stroke. Returns the slot index of the stroke, or an error if the path is empty.
+    pub fn add_brushstroke(&mut self, path: PenPath, style: Style) -> anyhow::Result<usize> {
+        let brushstroke = BrushStroke::from_penpath(path, style)
+            .ok_or_else(|| anyhow::anyhow!("can't add a brush stroke with an empty path"))?;
+
+        Ok(self.add_stroke(Stroke::BrushStroke(brushstroke)))
+    }
+
+    /// Adds a shape. Returns the slot index of the stroke.
+    pub fn add_shape(&mut self, shape: Shape, style: Style) -> usize {
+        self.add_stroke(Stroke::ShapeStroke(ShapeStroke::new(shape, style)))
+    }
+
+    /// Adds a bitmap or vector image. Returns the slot index of the stroke.
+    pub fn add_image(&mut self, image: impl Into<Stroke>) -> usize {
+        self.add_stroke(image.into())
+    }
+
+    /// Adds a stroke. Returns the slot index of the stroke.
+    pub fn add_stroke(&mut self, stroke: Stroke) -> usize {
+        self.store.insert_stroke(stroke)
+    }
+
+    /// Builds the document, fitting its extents to the strokes. See `RnotefileWrapper::fit_extents()`.
+    pub fn build(self) -> RnotefileWrapper {
+        let mut file = RnotefileWrapper::new(self.document, self.store);
+        file.fit_extents();
+
+        file
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rnotev0_5::tests::{line_stroke, rectangle_stroke, roundtrip};
+
+    #[test]
+    fn built_document_can_be_loaded() {
+        let mut builder =
+            DocumentBuilder::new(Format::default(), Layout::FixedSize, Background::default());
+        let first = builder.add_stroke(line_stroke(
+            na::vector![10.0, 10.0],
+            na::vector![50.0, 60.0],
+        ));
+        let second = builder.add_stroke(rectangle_stroke(
+            na::point![100.0, 100.0],
+            na::point![200.0, 150.0],
+        ));
+
+        let loaded = roundtrip(&builder.build());
+        let entries = loaded
+            .strokes()
+            .map(|(key, stroke, trashed, selected, chrono)| {
+                (key.index, stroke.type_name(), trashed, selected, chrono)
+            })
+            .collect::<Vec<_>>();
+
+        // the first slot is reserved
+        assert_eq!((first, second), (1, 2));
+        assert_eq!(
+            entries,
+            vec![
+                (1, "brushstroke", false, false, 1),
+                (2, "shapestroke", false, false, 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_document_can_be_loaded() {
+        let builder =
+            DocumentBuilder::new(Format::default(), Layout::Infinite, Background::default());
+
+        let loaded = roundtrip(&builder.build());
+        assert_eq!(loaded.strokes().count(), 0);
+    }
+}
@@ -6,9 +6,12 @@ use std::collections::VecDeque;
 use std::ops::{Deref, DerefMut};
 
 pub use element::Element;
+use p2d::bounding_volume::{BoundingVolume, AABB};
 pub use segment::Segment;
 use serde::{Deserialize, Serialize};
 
+use crate::rnotev0_5::{ShapeBehaviour, TransformBehaviour};
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename = "pen_path")]
 /// a pen path, consisting of segments of pen input elements
@@ -62,6 +65,31 @@ impl PenPath {
     }
 }
 
+impl TransformBehaviour for PenPath {
+    fn translate(&mut self, offset: na::Vector2<f64>) {
+        self.iter_mut()
+            .for_each(|segment| segment.translate(offset));
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        self.iter_mut().for_each(|segment| segment.scale(scale));
+    }
+
+    fn rotate(&mut self, angle: f64, center: na::Point2<f64>) {
+        self.iter_mut()
+            .for_each(|segment| segment.rotate(angle, center));
+    }
+}
+
+impl ShapeBehaviour for PenPath {
+    fn bounds(&self) -> AABB {
+        self.iter()
+            .map(|segment| segment.bounds())
+            .reduce(|acc, bounds| acc.merged(&bounds))
+            .unwrap_or_else(|| AABB::new(na::point![0.0, 0.0], na::point![0.0, 0.0]))
+    }
+}
+
 impl std::iter::FromIterator<Segment> for PenPath {
     fn from_iter<T: IntoIterator<Item = Segment>>(iter: T) -> Self {
         Self(VecDeque::from_iter(iter))
@@ -3,6 +3,8 @@ use std::collections::VecDeque;
 use p2d::bounding_volume::AABB;
 use serde::{Deserialize, Serialize};
 
+use crate::rnotev0_5::TransformBehaviour;
+
 /// A pen input element
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 #[serde(default, rename = "element")]
@@ -56,6 +58,23 @@ impl Element {
     }
 }
 
+impl TransformBehaviour for Element {
+    fn translate(&mut self, offset: na::Vector2<f64>) {
+        self.pos += offset;
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        self.pos = self.pos.component_mul(&scale);
+    }
+
+    fn rotate(&mut self, angle: f64, center: na::Point2<f64>) {
+        let mut isometry = na::Isometry2::identity();
+        isometry.append_rotation_wrt_point_mut(&na::UnitComplex::new(angle), &center);
+
+        self.transform_by(na::convert(isometry));
+    }
+}
+
 impl From<crate::rnotev0_4::strokes::element::Element> for Element {
     fn from(elem: crate::rnotev0_4::strokes::element::Element) -> Self {
         let crate::rnotev0_4::strokes::inputdata::InputData { pos, pressure } = elem.inputdata;
@@ -1,6 +1,11 @@
+use p2d::bounding_volume::AABB;
 use serde::{Deserialize, Serialize};
 
 use super::Element;
+use crate::rnotev0_5::shapes::cubbez::{cubbez_bounds, cubbez_calc};
+use crate::rnotev0_5::shapes::quadbez::{quadbez_bounds, quadbez_calc};
+use crate::rnotev0_5::shapes::{CubicBezier, QuadraticBezier};
+use crate::rnotev0_5::{ShapeBehaviour, TransformBehaviour};
 
 /// A single segment (usually of a path), containing elements to be able to being drawn with variable width
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -74,4 +79,211 @@ impl Segment {
             Segment::CubBez { end, .. } => *end,
         }
     }
+
+    /// The elements of the segment, mutable
+    pub fn elements_mut(&mut self) -> Vec<&mut Element> {
+        match self {
+            Segment::Dot { element } => vec![element],
+            Segment::Line { start, end }
+            | Segment::QuadBez { start, end, .. }
+            | Segment::CubBez { start, end, .. } => vec![start, end],
+        }
+    }
+
+    /// The position on the segment at t: between 0.0 and 1.0
+    pub fn point_at(&self, t: f64) -> na::Vector2<f64> {
+        match self {
+            Segment::Dot { element } => element.pos,
+            Segment::Line { start, end } => start.pos.lerp(&end.pos, t),
+            Segment::QuadBez { start, cp, end } => quadbez_calc(start.pos, *cp, end.pos, t),
+            Segment::CubBez {
+                start,
+                cp1,
+                cp2,
+                end,
+            } => cubbez_calc(start.pos, *cp1, *cp2, end.pos, t),
+        }
+    }
+
+    /// Splits the segment into two at t: between 0.0 and 1.0.
+    /// The pressure of the new element at the split is interpolated linearly. A dot is returned twice.
+    pub fn split(&self, t: f64) -> (Segment, Segment) {
+        match *self {
+            Segment::Dot { element } => (Segment::Dot { element }, Segment::Dot { element }),
+            Segment::Line { start, end } => {
+                let mid = lerp_element(start, end, self.point_at(t), t);
+
+                (
+                    Segment::Line { start, end: mid },
+                    Segment::Line { start: mid, end },
+                )
+            }
+            Segment::QuadBez { start, cp, end } => {
+                let (first, second) = QuadraticBezier {
+                    start: start.pos,
+                    cp,
+                    end: end.pos,
+                }
+                .split(t);
+                let mid = lerp_element(start, end, first.end, t);
+
+                (
+                    Segment::QuadBez {
+                        start,
+                        cp: first.cp,
+                        end: mid,
+                    },
+                    Segment::QuadBez {
+                        start: mid,
+                        cp: second.cp,
+                        end,
+                    },
+                )
+            }
+            Segment::CubBez {
+                start,
+                cp1,
+                cp2,
+                end,
+            } => {
+                let (first, second) = CubicBezier {
+                    start: start.pos,
+                    cp1,
+                    cp2,
+                    end: end.pos,
+                }
+                .split(t);
+                let mid = lerp_element(start, end, first.end, t);
+
+                (
+                    Segment::CubBez {
+                        start,
+                        cp1: first.cp1,
+                        cp2: first.cp2,
+                        end: mid,
+                    },
+                    Segment::CubBez {
+                        start: mid,
+                        cp1: second.cp1,
+                        cp2: second.cp2,
+                        end,
+                    },
+                )
+            }
+        }
+    }
+}
+
+/// An element at the position, with the pressure interpolated between the elements
+fn lerp_element(start: Element, end: Element, pos: na::Vector2<f64>, t: f64) -> Element {
+    Element::new(pos, start.pressure + (end.pressure - start.pressure) * t)
+}
+
+impl TransformBehaviour for Segment {
+    fn translate(&mut self, offset: na::Vector2<f64>) {
+        match self {
+            Segment::Dot { element } => {
+                element.translate(offset);
+            }
+            Segment::Line { start, end } => {
+                start.translate(offset);
+                end.translate(offset);
+            }
+            Segment::QuadBez { start, cp, end } => {
+                start.translate(offset);
+                *cp += offset;
+                end.translate(offset);
+            }
+            Segment::CubBez {
+                start,
+                cp1,
+                cp2,
+                end,
+            } => {
+                start.translate(offset);
+                *cp1 += offset;
+                *cp2 += offset;
+                end.translate(offset);
+            }
+        }
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        match self {
+            Segment::Dot { element } => {
+                element.scale(scale);
+            }
+            Segment::Line { start, end } => {
+                start.scale(scale);
+                end.scale(scale);
+            }
+            Segment::QuadBez { start, cp, end } => {
+                start.scale(scale);
+                *cp = cp.component_mul(&scale);
+                end.scale(scale);
+            }
+            Segment::CubBez {
+                start,
+                cp1,
+                cp2,
+                end,
+            } => {
+                start.scale(scale);
+                *cp1 = cp1.component_mul(&scale);
+                *cp2 = cp2.component_mul(&scale);
+                end.scale(scale);
+            }
+        }
+    }
+
+    fn rotate(&mut self, angle: f64, center: na::Point2<f64>) {
+        let mut isometry = na::Isometry2::identity();
+        isometry.append_rotation_wrt_point_mut(&na::UnitComplex::new(angle), &center);
+        let rotate_cp = |cp: &mut na::Vector2<f64>| *cp = (isometry * na::Point2::from(*cp)).coords;
+
+        match self {
+            Segment::Dot { element } => {
+                element.rotate(angle, center);
+            }
+            Segment::Line { start, end } => {
+                start.rotate(angle, center);
+                end.rotate(angle, center);
+            }
+            Segment::QuadBez { start, cp, end } => {
+                start.rotate(angle, center);
+                rotate_cp(cp);
+                end.rotate(angle, center);
+            }
+            Segment::CubBez {
+                start,
+                cp1,
+                cp2,
+                end,
+            } => {
+                start.rotate(angle, center);
+                rotate_cp(cp1);
+                rotate_cp(cp2);
+                end.rotate(angle, center);
+            }
+        }
+    }
+}
+
+impl ShapeBehaviour for Segment {
+    /// The tight bounds of the segment curve, not including the width.
+    fn bounds(&self) -> AABB {
+        match self {
+            Segment::Dot { element } => {
+                AABB::from_half_extents(element.pos.into(), na::Vector2::zeros())
+            }
+            Segment::Line { start, end } => AABB::from_points(&[start.pos.into(), end.pos.into()]),
+            Segment::QuadBez { start, cp, end } => quadbez_bounds(start.pos, *cp, end.pos),
+            Segment::CubBez {
+                start,
+                cp1,
+                cp2,
+                end,
+            } => cubbez_bounds(start.pos, *cp1, *cp2, end.pos),
+        }
+    }
 }
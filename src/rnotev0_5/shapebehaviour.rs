@@ -0,0 +1,7 @@
+use p2d::bounding_volume::AABB;
+
+/// Trait for types that have a shape in the coordinate space of the document
+pub trait ShapeBehaviour {
+    /// the bounds of the shape
+    fn bounds(&self) -> AABB;
+}
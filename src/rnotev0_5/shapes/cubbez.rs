@@ -1,7 +1,9 @@
+use p2d::bounding_volume::AABB;
 use serde::{Deserialize, Serialize};
 
 use super::line::Line;
 use super::quadbez::QuadraticBezier;
+use crate::rnotev0_5::{ShapeBehaviour, TransformBehaviour};
 
 #[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
 #[serde(default, rename = "cubic_bezier")]
@@ -113,6 +115,83 @@ impl CubicBezier {
     }
 }
 
+impl TransformBehaviour for CubicBezier {
+    fn translate(&mut self, offset: na::Vector2<f64>) {
+        self.start += offset;
+        self.cp1 += offset;
+        self.cp2 += offset;
+        self.end += offset;
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        self.start = self.start.component_mul(&scale);
+        self.cp1 = self.cp1.component_mul(&scale);
+        self.cp2 = self.cp2.component_mul(&scale);
+        self.end = self.end.component_mul(&scale);
+    }
+
+    fn rotate(&mut self, angle: f64, center: na::Point2<f64>) {
+        let mut isometry = na::Isometry2::identity();
+        isometry.append_rotation_wrt_point_mut(&na::UnitComplex::new(angle), &center);
+
+        self.start = (isometry * na::Point2::from(self.start)).coords;
+        self.cp1 = (isometry * na::Point2::from(self.cp1)).coords;
+        self.cp2 = (isometry * na::Point2::from(self.cp2)).coords;
+        self.end = (isometry * na::Point2::from(self.end)).coords;
+    }
+}
+
+impl ShapeBehaviour for CubicBezier {
+    /// The tight bounds of the curve.
+    fn bounds(&self) -> AABB {
+        cubbez_bounds(self.start, self.cp1, self.cp2, self.end)
+    }
+}
+
+/// The tight bounds of a cubic bezier curve, including its extrema
+pub fn cubbez_bounds(
+    p0: na::Vector2<f64>,
+    p1: na::Vector2<f64>,
+    p2: na::Vector2<f64>,
+    p3: na::Vector2<f64>,
+) -> AABB {
+    // the derivative (divided by 3) in polynomial form: a * t^2 + b * t + c
+    let a = -p0 + 3.0 * p1 - 3.0 * p2 + p3;
+    let b = 2.0 * (p0 - 2.0 * p1 + p2);
+    let c = p1 - p0;
+    let mut points: Vec<na::Point2<f64>> = vec![p0.into(), p3.into()];
+
+    for axis in 0..2 {
+        let roots = if a[axis].abs() < f64::EPSILON {
+            if b[axis] == 0.0 {
+                vec![]
+            } else {
+                vec![-c[axis] / b[axis]]
+            }
+        } else {
+            let discriminant = b[axis].powi(2) - 4.0 * a[axis] * c[axis];
+            if discriminant < 0.0 {
+                vec![]
+            } else {
+                let sqrt = discriminant.sqrt();
+                vec![
+                    (-b[axis] + sqrt) / (2.0 * a[axis]),
+                    (-b[axis] - sqrt) / (2.0 * a[axis]),
+                ]
+            }
+        };
+
+        points.extend(
+            roots
+                .into_iter()
+                .filter(|t| *t > 0.0 && *t < 1.0)
+                .map(|t| na::Point2::from(cubbez_calc(p0, p1, p2, p3, t))),
+        );
+    }
+
+    AABB::from_points(&points)
+}
+
 /// Calculates a point on a cubic curve given t ranging [0.0, 1.0]
 pub fn cubbez_calc(
     p0: na::Vector2<f64>,
@@ -137,3 +216,22 @@ pub fn cubbez_calc(
     (na::vector![1.0, t, t.powi(2), t.powi(3)].transpose() * transform_matrix * p_matrix)
         .transpose()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounds_are_tight_around_the_extrema() {
+        let bounds = cubbez_bounds(
+            na::vector![0.0, 0.0],
+            na::vector![0.0, 10.0],
+            na::vector![10.0, 10.0],
+            na::vector![10.0, 0.0],
+        );
+
+        // the control points are not reached, the curve peaks at t = 0.5
+        assert_eq!(bounds.mins, na::point![0.0, 0.0]);
+        assert!((bounds.maxs - na::point![10.0, 7.5]).magnitude() < 1e-9);
+    }
+}
@@ -1,6 +1,7 @@
+use p2d::bounding_volume::AABB;
 use serde::{Deserialize, Serialize};
 
-use crate::rnotev0_5::Transform;
+use crate::rnotev0_5::{ShapeBehaviour, Transform, TransformBehaviour};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename = "ellipse")]
@@ -23,6 +24,33 @@ impl Default for Ellipse {
     }
 }
 
+impl TransformBehaviour for Ellipse {
+    fn translate(&mut self, offset: na::Vector2<f64>) {
+        self.transform.append_translation_mut(offset);
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        self.transform.append_scale_mut(scale);
+    }
+
+    fn rotate(&mut self, angle: f64, center: na::Point2<f64>) {
+        self.transform.append_rotation_wrt_point_mut(angle, center);
+    }
+}
+
+impl ShapeBehaviour for Ellipse {
+    fn bounds(&self) -> AABB {
+        let center = self.transform.affine * na::point![0.0, 0.0];
+        // using a vector to ignore the translation
+        let half_extents = na::Vector2::from_homogeneous(
+            self.transform.affine.into_inner().abs() * self.radii.to_homogeneous(),
+        )
+        .unwrap();
+
+        AABB::from_half_extents(center, half_extents)
+    }
+}
+
 impl From<crate::rnotev0_4::shapes::Ellipse> for Ellipse {
     fn from(ellipse: crate::rnotev0_4::shapes::Ellipse) -> Self {
         Self {
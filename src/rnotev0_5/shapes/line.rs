@@ -1,7 +1,8 @@
+use p2d::bounding_volume::AABB;
 use serde::{Deserialize, Serialize};
 
 use super::Rectangle;
-use crate::rnotev0_5::Transform;
+use crate::rnotev0_5::{ShapeBehaviour, Transform, TransformBehaviour};
 
 #[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
 #[serde(default, rename = "line")]
@@ -48,6 +49,32 @@ impl Line {
     }
 }
 
+impl TransformBehaviour for Line {
+    fn translate(&mut self, offset: na::Vector2<f64>) {
+        self.start += offset;
+        self.end += offset;
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        self.start = self.start.component_mul(&scale);
+        self.end = self.end.component_mul(&scale);
+    }
+
+    fn rotate(&mut self, angle: f64, center: na::Point2<f64>) {
+        let mut isometry = na::Isometry2::identity();
+        isometry.append_rotation_wrt_point_mut(&na::UnitComplex::new(angle), &center);
+
+        self.start = (isometry * na::Point2::from(self.start)).coords;
+        self.end = (isometry * na::Point2::from(self.end)).coords;
+    }
+}
+
+impl ShapeBehaviour for Line {
+    fn bounds(&self) -> AABB {
+        AABB::from_points(&[self.start.into(), self.end.into()])
+    }
+}
+
 impl From<crate::rnotev0_4::curves::Line> for Line {
     fn from(line: crate::rnotev0_4::curves::Line) -> Self {
         Self {
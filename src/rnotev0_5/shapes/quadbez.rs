@@ -1,7 +1,9 @@
+use p2d::bounding_volume::AABB;
 use serde::{Deserialize, Serialize};
 
 use super::line::Line;
 use super::CubicBezier;
+use crate::rnotev0_5::{ShapeBehaviour, TransformBehaviour};
 
 #[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
 #[serde(default, rename = "quadratic_bezier")]
@@ -79,6 +81,55 @@ impl QuadraticBezier {
     }
 }
 
+impl TransformBehaviour for QuadraticBezier {
+    fn translate(&mut self, offset: na::Vector2<f64>) {
+        self.start += offset;
+        self.cp += offset;
+        self.end += offset;
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        self.start = self.start.component_mul(&scale);
+        self.cp = self.cp.component_mul(&scale);
+        self.end = self.end.component_mul(&scale);
+    }
+
+    fn rotate(&mut self, angle: f64, center: na::Point2<f64>) {
+        let mut isometry = na::Isometry2::identity();
+        isometry.append_rotation_wrt_point_mut(&na::UnitComplex::new(angle), &center);
+
+        self.start = (isometry * na::Point2::from(self.start)).coords;
+        self.cp = (isometry * na::Point2::from(self.cp)).coords;
+        self.end = (isometry * na::Point2::from(self.end)).coords;
+    }
+}
+
+impl ShapeBehaviour for QuadraticBezier {
+    /// The tight bounds of the curve.
+    fn bounds(&self) -> AABB {
+        quadbez_bounds(self.start, self.cp, self.end)
+    }
+}
+
+/// The tight bounds of a quadratic bezier curve, including its extrema
+pub fn quadbez_bounds(p0: na::Vector2<f64>, p1: na::Vector2<f64>, p2: na::Vector2<f64>) -> AABB {
+    let a = quad_bezier_derive_coeff_a(p0, p1, p2);
+    let b = quadbez_derive_coeff_b(p0, p1);
+    let mut points: Vec<na::Point2<f64>> = vec![p0.into(), p2.into()];
+
+    // the derivative a * t + b is zero at the extrema of each axis
+    for axis in 0..2 {
+        if a[axis] != 0.0 {
+            let t = -b[axis] / a[axis];
+            if t > 0.0 && t < 1.0 {
+                points.push(quadbez_calc(p0, p1, p2, t).into());
+            }
+        }
+    }
+
+    AABB::from_points(&points)
+}
+
 /// Coefficient a of quadratic bezier in polynomial form: C = a * t^2 + b * t + c
 pub fn quadbez_coeff_a(
     p0: na::Vector2<f64>,
@@ -100,7 +151,6 @@ pub fn quadbez_coeff_c(p0: na::Vector2<f64>) -> na::Vector2<f64> {
 }
 
 /// calculating the value of a bezier curve with its support points, for t: between 0.0 and 1.0
-#[allow(dead_code)]
 pub fn quadbez_calc(
     p0: na::Vector2<f64>,
     p1: na::Vector2<f64>,
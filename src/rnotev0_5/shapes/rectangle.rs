@@ -2,7 +2,7 @@ use p2d::bounding_volume::AABB;
 use serde::{Deserialize, Serialize};
 
 use super::Line;
-use crate::rnotev0_5::Transform;
+use crate::rnotev0_5::{ShapeBehaviour, Transform, TransformBehaviour};
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 #[serde(default, rename = "rectangle")]
@@ -34,6 +34,18 @@ impl Rectangle {
         Self { cuboid, transform }
     }
 
+    /// The size of the rect in the coordinate space of the doc
+    pub fn size(&self) -> na::Vector2<f64> {
+        na::vector![
+            self.transform
+                .transform_vec(na::vector![2.0 * self.cuboid.half_extents[0], 0.0])
+                .magnitude(),
+            self.transform
+                .transform_vec(na::vector![0.0, 2.0 * self.cuboid.half_extents[1]])
+                .magnitude()
+        ]
+    }
+
     /// The outline lines of the rect
     pub fn outline_lines(&self) -> [Line; 4] {
         let upper_left = self.transform.transform_point(na::point![
@@ -74,6 +86,33 @@ impl Rectangle {
     }
 }
 
+impl TransformBehaviour for Rectangle {
+    fn translate(&mut self, offset: na::Vector2<f64>) {
+        self.transform.append_translation_mut(offset);
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        self.transform.append_scale_mut(scale);
+    }
+
+    fn rotate(&mut self, angle: f64, center: na::Point2<f64>) {
+        self.transform.append_rotation_wrt_point_mut(angle, center);
+    }
+}
+
+impl ShapeBehaviour for Rectangle {
+    fn bounds(&self) -> AABB {
+        let center = self.transform.affine * na::point![0.0, 0.0];
+        // using a vector to ignore the translation
+        let half_extents = na::Vector2::from_homogeneous(
+            self.transform.affine.into_inner().abs() * self.cuboid.half_extents.to_homogeneous(),
+        )
+        .unwrap();
+
+        AABB::from_half_extents(center, half_extents)
+    }
+}
+
 impl From<crate::rnotev0_4::shapes::Rectangle> for Rectangle {
     fn from(rect: crate::rnotev0_4::shapes::Rectangle) -> Self {
         // Self::from_p2d_aabb(rect.global_aabb())
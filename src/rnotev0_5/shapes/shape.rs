@@ -1,7 +1,9 @@
+use p2d::bounding_volume::AABB;
 use serde::{Deserialize, Serialize};
 
 use super::{CubicBezier, Ellipse, Line, QuadraticBezier, Rectangle};
 use crate::rnotev0_5::penpath::Segment;
+use crate::rnotev0_5::{ShapeBehaviour, TransformBehaviour};
 
 // Container type to store shapes
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,6 +36,54 @@ impl Default for Shape {
     }
 }
 
+impl TransformBehaviour for Shape {
+    fn translate(&mut self, offset: na::Vector2<f64>) {
+        match self {
+            Self::Line(line) => line.translate(offset),
+            Self::Rectangle(rectangle) => rectangle.translate(offset),
+            Self::Ellipse(ellipse) => ellipse.translate(offset),
+            Self::QuadraticBezier(quadbez) => quadbez.translate(offset),
+            Self::CubicBezier(cubbez) => cubbez.translate(offset),
+            Self::Segment(segment) => segment.translate(offset),
+        }
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        match self {
+            Self::Line(line) => line.scale(scale),
+            Self::Rectangle(rectangle) => rectangle.scale(scale),
+            Self::Ellipse(ellipse) => ellipse.scale(scale),
+            Self::QuadraticBezier(quadbez) => quadbez.scale(scale),
+            Self::CubicBezier(cubbez) => cubbez.scale(scale),
+            Self::Segment(segment) => segment.scale(scale),
+        }
+    }
+
+    fn rotate(&mut self, angle: f64, center: na::Point2<f64>) {
+        match self {
+            Self::Line(line) => line.rotate(angle, center),
+            Self::Rectangle(rectangle) => rectangle.rotate(angle, center),
+            Self::Ellipse(ellipse) => ellipse.rotate(angle, center),
+            Self::QuadraticBezier(quadbez) => quadbez.rotate(angle, center),
+            Self::CubicBezier(cubbez) => cubbez.rotate(angle, center),
+            Self::Segment(segment) => segment.rotate(angle, center),
+        }
+    }
+}
+
+impl ShapeBehaviour for Shape {
+    fn bounds(&self) -> AABB {
+        match self {
+            Self::Line(line) => line.bounds(),
+            Self::Rectangle(rectangle) => rectangle.bounds(),
+            Self::Ellipse(ellipse) => ellipse.bounds(),
+            Self::QuadraticBezier(quadbez) => quadbez.bounds(),
+            Self::CubicBezier(cubbez) => cubbez.bounds(),
+            Self::Segment(segment) => segment.bounds(),
+        }
+    }
+}
+
 impl From<crate::rnotev0_4::strokes::shapestroke::Shape> for Shape {
     fn from(shape: crate::rnotev0_4::strokes::shapestroke::Shape) -> Self {
         match shape {
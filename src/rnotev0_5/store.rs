@@ -1,8 +1,13 @@
+use std::ops::RangeInclusive;
+
+use anyhow::Context;
+use p2d::bounding_volume::{BoundingVolume, AABB};
 use serde::{Deserialize, Serialize};
 
-use super::stroke::Stroke;
+use super::stroke::{Stroke, StrokeType};
+use super::ShapeBehaviour;
 pub use crate::rnotev0_4::{ChronoComponent, SelectionComponent, TrashComponent};
-use crate::slot::{SerdeSlot, SlotMap};
+use crate::slot::{self, SerdeSlot, SlotMap};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename = "history_entry")]
@@ -22,21 +27,477 @@ pub struct HistoryEntry {
 
 impl Default for HistoryEntry {
     fn default() -> Self {
+        // the first slot of every slot map is reserved and stays vacant
         Self {
-            stroke_components: Vec::new(),
-            trash_components: Vec::new(),
-            selection_components: Vec::new(),
-            chrono_components: Vec::new(),
+            stroke_components: vec![SerdeSlot::vacant()],
+            trash_components: vec![SerdeSlot::vacant()],
+            selection_components: vec![SerdeSlot::vacant()],
+            chrono_components: vec![SerdeSlot::vacant()],
             chrono_counter: 0,
         }
     }
 }
 
+impl HistoryEntry {
+    /// The stroke components, indexed by their slot
+    pub fn stroke_components(&self) -> &SlotMap<Stroke> {
+        &self.stroke_components
+    }
+
+    /// The trash components, indexed by their slot
+    pub fn trash_components(&self) -> &SlotMap<TrashComponent> {
+        &self.trash_components
+    }
+
+    /// The selection components, indexed by their slot
+    pub fn selection_components(&self) -> &SlotMap<SelectionComponent> {
+        &self.selection_components
+    }
+
+    /// The chrono components, indexed by their slot
+    pub fn chrono_components(&self) -> &SlotMap<ChronoComponent> {
+        &self.chrono_components
+    }
+
+    /// The chrono counter, equal to the chrono component of the newest inserted or modified stroke
+    pub fn chrono_counter(&self) -> u32 {
+        self.chrono_counter
+    }
+
+    /// Iterates over the existing strokes, joined with their trashed and selected state and their chrono component.
+    /// Missing components are treated as not trashed, not selected and chrono 0.
+    pub fn strokes(&self) -> impl Iterator<Item = StrokeEntry<'_>> {
+        self.stroke_components
+            .iter()
+            .enumerate()
+            .filter_map(move |(index, slot)| {
+                let stroke = slot.value.as_ref()?;
+                let key = StrokeKey {
+                    index,
+                    version: slot.version,
+                };
+                let selected = self
+                    .selection_components
+                    .get(index)
+                    .and_then(|slot| slot.value)
+                    .map(|selection| selection.selected)
+                    .unwrap_or(false);
+                let chrono = self
+                    .chrono_components
+                    .get(index)
+                    .and_then(|slot| slot.value)
+                    .map(|chrono| chrono.t)
+                    .unwrap_or(0);
+
+                Some((key, stroke, self.is_trashed(index), selected, chrono))
+            })
+    }
+
+    /// Iterates over the existing strokes which match the filter
+    pub fn strokes_filtered<'a>(
+        &'a self,
+        filter: &'a StrokeFilter,
+    ) -> impl Iterator<Item = StrokeEntry<'a>> {
+        self.strokes().filter(|entry| filter.matches(entry))
+    }
+
+    /// The merged bounds of the strokes which are not trashed, or None if there are none
+    pub fn strokes_bounds(&self) -> Option<AABB> {
+        self.strokes()
+            .filter(|(_, _, trashed, _, _)| !trashed)
+            .map(|(_, stroke, _, _, _)| stroke.bounds())
+            .reduce(|acc, bounds| acc.merged(&bounds))
+    }
+
+    /// The stroke at the slot index, mutable
+    pub fn stroke_mut(&mut self, index: usize) -> Option<&mut Stroke> {
+        self.stroke_components
+            .get_mut(index)
+            .and_then(|slot| slot.value.as_mut())
+    }
+
+    /// Iterates mutably over the existing strokes
+    pub fn strokes_mut(&mut self) -> impl Iterator<Item = &mut Stroke> {
+        self.stroke_components
+            .iter_mut()
+            .filter_map(|slot| slot.value.as_mut())
+    }
+
+    /// Appends the strokes of another store snapshot, placing them in new slots after the existing ones.
+    /// The chrono components of the appended strokes are offset, so that they are ordered after the existing strokes.
+    pub fn append(&mut self, mut other: HistoryEntry) {
+        self.pad_components();
+        other.pad_components();
+
+        let chrono_offset = self.chrono_counter;
+        other
+            .chrono_components
+            .iter_mut()
+            .filter_map(|slot| slot.value.as_mut())
+            .for_each(|chrono| chrono.t += chrono_offset);
+
+        self.stroke_components.append(&mut other.stroke_components);
+        self.trash_components.append(&mut other.trash_components);
+        self.selection_components
+            .append(&mut other.selection_components);
+        self.chrono_components.append(&mut other.chrono_components);
+        self.chrono_counter += other.chrono_counter;
+    }
+
+    /// Creates a new store snapshot from clones of the strokes at the given slot indices, in the given order.
+    /// The strokes are placed after the reserved vacant first slot.
+    pub fn clone_slots(&self, indices: &[usize]) -> Self {
+        fn clone_from<T: Clone>(slots: &SlotMap<T>, indices: &[usize]) -> SlotMap<T> {
+            std::iter::once(SerdeSlot::vacant())
+                .chain(
+                    indices
+                        .iter()
+                        .map(|&index| slots.get(index).cloned().unwrap_or_else(SerdeSlot::vacant)),
+                )
+                .collect()
+        }
+
+        Self {
+            stroke_components: clone_from(&self.stroke_components, indices),
+            trash_components: clone_from(&self.trash_components, indices),
+            selection_components: clone_from(&self.selection_components, indices),
+            chrono_components: clone_from(&self.chrono_components, indices),
+            chrono_counter: self.chrono_counter,
+        }
+    }
+
+    /// Removes trashed strokes and vacant slots, compacting the slot maps. The reserved first slot stays vacant.
+    ///
+    /// The versions of the remaining slots are reset to the first occupied version,
+    /// and their chrono components are renumbered from 1 while preserving their order.
+    /// Returns the number of removed trashed strokes and the number of removed vacant slots.
+    pub fn compact(&mut self) -> (usize, usize) {
+        self.pad_components();
+
+        let mut n_trashed = 0;
+        let mut n_vacant = 0;
+        let keep = (0..self.stroke_components.len())
+            .map(|index| {
+                if self.stroke_components[index].value.is_none() {
+                    // the reserved first slot is kept
+                    if index > 0 {
+                        n_vacant += 1;
+                    }
+                    false
+                } else if self.is_trashed(index) {
+                    n_trashed += 1;
+                    false
+                } else {
+                    true
+                }
+            })
+            .collect::<Vec<bool>>();
+
+        fn retain<T>(
+            slots: &mut SlotMap<T>,
+            keep: &[bool],
+            value: impl Fn(Option<T>) -> Option<T>,
+        ) {
+            let kept = std::mem::take(slots)
+                .into_iter()
+                .enumerate()
+                .filter(|(index, _)| keep[*index])
+                .map(|(_, slot)| SerdeSlot {
+                    value: value(slot.value),
+                    version: 1,
+                });
+            *slots = std::iter::once(SerdeSlot::vacant()).chain(kept).collect();
+        }
+        retain(&mut self.stroke_components, &keep, |stroke| stroke);
+        retain(&mut self.trash_components, &keep, |_| {
+            Some(TrashComponent { trashed: false })
+        });
+        retain(&mut self.selection_components, &keep, |selection| {
+            Some(selection.unwrap_or_default())
+        });
+        retain(&mut self.chrono_components, &keep, |chrono| {
+            Some(chrono.unwrap_or_default())
+        });
+        self.renumber_chrono();
+
+        (n_trashed, n_vacant)
+    }
+
+    /// Renumbers the chrono components of the existing strokes from 1, preserving their order.
+    /// The chrono counter is set to the newest chrono component.
+    pub fn renumber_chrono(&mut self) {
+        self.renumber_chrono_with_back(&[]);
+    }
+
+    /// Moves the strokes at the slot indices behind all other strokes, by renumbering the chrono components from 1.
+    /// The order within the moved and within the other strokes is preserved.
+    pub fn send_to_back(&mut self, indices: &[usize]) {
+        self.renumber_chrono_with_back(indices);
+    }
+
+    fn renumber_chrono_with_back(&mut self, back: &[usize]) {
+        self.pad_components();
+
+        let chrono_t = |index: usize| {
+            self.chrono_components[index]
+                .value
+                .map(|chrono| chrono.t)
+                .unwrap_or(0)
+        };
+        let mut in_back = vec![false; self.stroke_components.len()];
+        for &index in back {
+            if let Some(in_back) = in_back.get_mut(index) {
+                *in_back = true;
+            }
+        }
+        // the stable sort keeps the slot order for equal chrono components
+        let mut chrono_order = (0..self.stroke_components.len())
+            .filter(|&index| self.stroke_components[index].value.is_some())
+            .collect::<Vec<usize>>();
+        chrono_order.sort_by_key(|&index| (!in_back[index], chrono_t(index)));
+
+        for (i, &index) in chrono_order.iter().enumerate() {
+            self.chrono_components[index] = SerdeSlot {
+                value: Some(ChronoComponent::new(i as u32 + 1)),
+                version: self.stroke_components[index].version,
+            };
+        }
+        self.chrono_counter = chrono_order.len() as u32;
+    }
+
+    /// Inserts the stroke into a new slot with fresh components, ordering it after the existing strokes.
+    /// Returns the slot index of the inserted stroke.
+    pub fn insert_stroke(&mut self, stroke: Stroke) -> usize {
+        self.pad_components();
+        self.chrono_counter += 1;
+
+        fn push<T>(slots: &mut SlotMap<T>, value: T) {
+            slots.push(SerdeSlot {
+                value: Some(value),
+                version: 1,
+            });
+        }
+        push(&mut self.stroke_components, stroke);
+        push(&mut self.trash_components, TrashComponent::default());
+        push(
+            &mut self.selection_components,
+            SelectionComponent::default(),
+        );
+        push(
+            &mut self.chrono_components,
+            ChronoComponent::new(self.chrono_counter),
+        );
+
+        self.stroke_components.len() - 1
+    }
+
+    /// Replaces the stroke in an existing slot and orders it after the existing strokes.
+    /// The slot version is kept, as it marks the slot as occupied.
+    /// Returns the replaced stroke, or None if the slot is vacant or does not exist.
+    pub fn replace_stroke(&mut self, index: usize, stroke: Stroke) -> Option<Stroke> {
+        self.pad_components();
+        let slot = self
+            .stroke_components
+            .get_mut(index)
+            .filter(|slot| slot.value.is_some())?;
+        let replaced = slot.value.replace(stroke);
+        let version = slot.version;
+
+        self.chrono_counter += 1;
+        self.chrono_components[index] = SerdeSlot {
+            value: Some(ChronoComponent::new(self.chrono_counter)),
+            version,
+        };
+        replaced
+    }
+
+    /// Removes the stroke and its components, leaving a vacant slot.
+    /// Returns the removed stroke, or None if the slot is vacant or does not exist.
+    pub fn remove_stroke(&mut self, index: usize) -> Option<Stroke> {
+        self.pad_components();
+        let slot = self
+            .stroke_components
+            .get_mut(index)
+            .filter(|slot| slot.value.is_some())?;
+        let removed = slot.value.take();
+        // the even version marks the slots as vacant
+        slot.version += 1;
+        let version = slot.version;
+
+        self.trash_components[index] = SerdeSlot {
+            value: None,
+            version,
+        };
+        self.selection_components[index] = SerdeSlot {
+            value: None,
+            version,
+        };
+        self.chrono_components[index] = SerdeSlot {
+            value: None,
+            version,
+        };
+        removed
+    }
+
+    /// Sets the trashed state of the stroke. Returns false if the slot is vacant or does not exist.
+    pub fn set_trashed(&mut self, index: usize, trashed: bool) -> bool {
+        self.pad_components();
+        if self.stroke_mut(index).is_none() {
+            return false;
+        }
+        self.trash_components[index] = SerdeSlot {
+            value: Some(TrashComponent { trashed }),
+            version: self.stroke_components[index].version,
+        };
+        true
+    }
+
+    /// Sets the selected state of the stroke. Returns false if the slot is vacant or does not exist.
+    pub fn set_selected(&mut self, index: usize, selected: bool) -> bool {
+        self.pad_components();
+        if self.stroke_mut(index).is_none() {
+            return false;
+        }
+        self.selection_components[index] = SerdeSlot {
+            value: Some(SelectionComponent { selected }),
+            version: self.stroke_components[index].version,
+        };
+        true
+    }
+
+    /// Deselects all strokes. Returns the number of strokes which were selected.
+    pub fn clear_selection(&mut self) -> usize {
+        self.selection_components
+            .iter_mut()
+            .filter_map(|slot| slot.value.as_mut())
+            .filter(|selection| selection.selected)
+            .map(|selection| selection.selected = false)
+            .count()
+    }
+
+    /// Asserts that the slot maps of all components can be loaded by Rnote. See `slot::assert_valid()`.
+    pub fn assert_valid(&self) -> anyhow::Result<()> {
+        slot::assert_valid(&self.stroke_components).context("invalid stroke components")?;
+        slot::assert_valid(&self.trash_components).context("invalid trash components")?;
+        slot::assert_valid(&self.selection_components).context("invalid selection components")?;
+        slot::assert_valid(&self.chrono_components).context("invalid chrono components")?;
+
+        Ok(())
+    }
+
+    /// Pads the component slot maps with vacant slots or truncates them, so that all have the length of the stroke
+    /// components. Components beyond the stroke components belong to no stroke. Empty stroke components get the
+    /// reserved vacant first slot.
+    fn pad_components(&mut self) {
+        fn pad<T>(slots: &mut SlotMap<T>, len: usize) {
+            slots.truncate(len);
+            while slots.len() < len {
+                slots.push(SerdeSlot::vacant());
+            }
+        }
+        if self.stroke_components.is_empty() {
+            self.stroke_components.push(SerdeSlot::vacant());
+        }
+        let len = self.stroke_components.len();
+
+        pad(&mut self.trash_components, len);
+        pad(&mut self.selection_components, len);
+        pad(&mut self.chrono_components, len);
+    }
+
+    /// Returns true if the stroke at the slot index exists and is trashed
+    pub fn is_trashed(&self, index: usize) -> bool {
+        self.trash_components
+            .get(index)
+            .and_then(|slot| slot.value)
+            .map(|trash| trash.trashed)
+            .unwrap_or(false)
+    }
+}
+
+/// The key of a stroke, consisting of its slot index and the slot version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct StrokeKey {
+    pub index: usize,
+    pub version: u32,
+}
+
+/// A stroke joined with its components: `(key, stroke, trashed, selected, chrono)`
+pub type StrokeEntry<'a> = (StrokeKey, &'a Stroke, bool, bool, u32);
+
+/// A filter for the strokes of a store. Unset criteria match all strokes.
+#[derive(Debug, Clone, Default)]
+pub struct StrokeFilter {
+    /// The stroke types
+    pub variants: Option<Vec<StrokeType>>,
+    /// The bounds which the strokes must intersect
+    pub bounds: Option<AABB>,
+    /// The range of the chrono components
+    pub chrono: Option<RangeInclusive<u32>>,
+    /// The selected state of the strokes
+    pub selected: Option<bool>,
+}
+
+impl StrokeFilter {
+    /// Only match strokes of the given type. Can be called multiple times.
+    pub fn variant(mut self, stroke_type: StrokeType) -> Self {
+        self.variants.get_or_insert_with(Vec::new).push(stroke_type);
+        self
+    }
+
+    /// Only match strokes whose bounds intersect the given bounds
+    pub fn intersecting(mut self, bounds: AABB) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    /// Only match strokes whose chrono component is in the range
+    pub fn chrono_range(mut self, chrono: RangeInclusive<u32>) -> Self {
+        self.chrono = Some(chrono);
+        self
+    }
+
+    /// Only match strokes which are selected, or which are not selected
+    pub fn selected(mut self, selected: bool) -> Self {
+        self.selected = Some(selected);
+        self
+    }
+
+    /// Returns true if the entry matches all criteria of the filter
+    pub fn matches(&self, (_, stroke, _, selected, chrono): &StrokeEntry) -> bool {
+        self.selected.is_none_or(|s| s == *selected)
+            && self
+                .variants
+                .as_ref()
+                .is_none_or(|variants| variants.contains(&stroke.stroke_type()))
+            && self
+                .chrono
+                .as_ref()
+                .is_none_or(|range| range.contains(chrono))
+            && self
+                .bounds
+                .is_none_or(|bounds| bounds.intersects(&stroke.bounds()))
+    }
+}
+
 // the store snapshot, used when saving the store to a file.
 pub type StoreSnapshot = HistoryEntry;
 
 impl From<crate::rnotev0_4::StrokesState> for HistoryEntry {
     fn from(ss: crate::rnotev0_4::StrokesState) -> Self {
+        // v0.4 markers are converted to smooth strokes, which have to be ordered behind the other strokes to keep
+        // the highlighter look
+        let markers = ss
+            .strokes
+            .iter()
+            .map(|s| match &s.value {
+                Some(crate::rnotev0_4::StrokeStyle::BrushStroke(bs)) => matches!(
+                    bs.style,
+                    crate::rnotev0_4::strokes::brushstroke::BrushStrokeStyle::Marker { .. }
+                ),
+                _ => false,
+            })
+            .collect::<Vec<bool>>();
         let mut remove_list = vec![];
         let stroke_components = ss
             .strokes
@@ -70,12 +531,220 @@ impl From<crate::rnotev0_4::StrokesState> for HistoryEntry {
             vec
         }
 
-        Self {
+        let mut store = Self {
             stroke_components,
             trash_components: remove_from_vec(&remove_list, ss.trash_components),
             selection_components: remove_from_vec(&remove_list, ss.selection_components),
             chrono_components: remove_from_vec(&remove_list, ss.chrono_components),
             chrono_counter: ss.chrono_counter,
+        };
+
+        let marker_indices = remove_from_vec(&remove_list, markers)
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, marker)| marker.then_some(index))
+            .collect::<Vec<usize>>();
+        if !marker_indices.is_empty() {
+            store.send_to_back(&marker_indices);
+        }
+
+        store
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rnotev0_4::strokes::brushstroke::{BrushStroke as BrushStrokeV4, BrushStrokeStyle};
+    use crate::rnotev0_4::strokes::element::Element as ElementV4;
+    use crate::rnotev0_4::strokes::SmoothOptions as SmoothOptionsV4;
+    use crate::rnotev0_4::Background;
+    use crate::rnotev0_5::stroke::{BrushStroke, Style};
+    use crate::rnotev0_5::tests::{line_stroke, rectangle_stroke};
+    use crate::rnotev0_5::{DocumentBuilder, Format, Layout};
+
+    /// A store with a line, a rectangle and another line, where the rectangle is selected
+    fn store() -> StoreSnapshot {
+        let mut builder =
+            DocumentBuilder::new(Format::default(), Layout::Infinite, Background::default());
+        builder.add_stroke(line_stroke(na::vector![0.0, 0.0], na::vector![10.0, 0.0]));
+        let rectangle = builder.add_stroke(rectangle_stroke(
+            na::point![100.0, 100.0],
+            na::point![200.0, 200.0],
+        ));
+        builder.add_stroke(line_stroke(
+            na::vector![300.0, 300.0],
+            na::vector![310.0, 300.0],
+        ));
+        let (_, mut store) = builder.build().into_parts();
+        store.set_selected(rectangle, true);
+        store
+    }
+
+    fn filtered(store: &StoreSnapshot, filter: StrokeFilter) -> Vec<usize> {
+        store
+            .strokes_filtered(&filter)
+            .map(|(key, ..)| key.index)
+            .collect()
+    }
+
+    #[test]
+    fn empty_filter_matches_all_strokes() {
+        assert_eq!(filtered(&store(), StrokeFilter::default()), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn filter_criteria_are_combined() {
+        let store = store();
+
+        assert_eq!(
+            filtered(
+                &store,
+                StrokeFilter::default().variant(StrokeType::BrushStroke)
+            ),
+            vec![1, 3]
+        );
+        assert_eq!(
+            filtered(
+                &store,
+                StrokeFilter::default()
+                    .variant(StrokeType::ShapeStroke)
+                    .variant(StrokeType::BitmapImage)
+            ),
+            vec![2]
+        );
+        assert_eq!(
+            filtered(
+                &store,
+                StrokeFilter::default()
+                    .intersecting(AABB::new(na::point![5.0, -5.0], na::point![150.0, 150.0]))
+            ),
+            vec![1, 2]
+        );
+        assert_eq!(
+            filtered(&store, StrokeFilter::default().chrono_range(2..=3)),
+            vec![2, 3]
+        );
+        assert_eq!(
+            filtered(&store, StrokeFilter::default().selected(false)),
+            vec![1, 3]
+        );
+        assert_eq!(
+            filtered(
+                &store,
+                StrokeFilter::default()
+                    .variant(StrokeType::BrushStroke)
+                    .chrono_range(2..=3)
+            ),
+            vec![3]
+        );
+    }
+
+    #[test]
+    fn strokes_bounds_skip_trashed_strokes() {
+        let mut store = store();
+        assert_eq!(
+            store.strokes_bounds().unwrap().maxs,
+            na::point![310.25, 300.25]
+        );
+
+        store.set_trashed(3, true);
+        assert_eq!(
+            store.strokes_bounds().unwrap().mins,
+            na::point![-0.25, -0.25]
+        );
+        assert_eq!(
+            store.strokes_bounds().unwrap().maxs,
+            na::point![200.5, 200.5]
+        );
+    }
+
+    #[test]
+    fn surplus_components_are_dropped_when_compacting() {
+        let mut store = store();
+        store.trash_components.push(SerdeSlot {
+            value: Some(TrashComponent { trashed: true }),
+            version: 1,
+        });
+        store.chrono_components.push(SerdeSlot::vacant());
+
+        assert_eq!(store.compact(), (0, 0));
+        assert_eq!(store.trash_components.len(), 4);
+        assert_eq!(store.chrono_components.len(), 4);
+        store.assert_valid().unwrap();
+    }
+
+    fn chrono_order(store: &StoreSnapshot) -> Vec<usize> {
+        let mut entries: Vec<(u32, usize)> = store
+            .strokes()
+            .map(|(key, _, _, _, chrono)| (chrono, key.index))
+            .collect();
+        entries.sort();
+        entries.into_iter().map(|(_, index)| index).collect()
+    }
+
+    #[test]
+    fn sent_to_back_strokes_keep_their_order() {
+        let mut store = store();
+
+        store.send_to_back(&[3, 1]);
+
+        assert_eq!(chrono_order(&store), vec![1, 3, 2]);
+        assert_eq!(store.chrono_counter(), 3);
+        store.assert_valid().unwrap();
+    }
+
+    #[test]
+    fn converted_markers_are_ordered_behind_other_strokes() {
+        let mut ss = crate::rnotev0_4::StrokesState::default();
+        fn push<T>(slots: &mut SlotMap<T>, value: Option<T>) {
+            slots.push(SerdeSlot {
+                version: if value.is_some() { 1 } else { 0 },
+                value,
+            });
+        }
+        push(&mut ss.strokes, None);
+        push(&mut ss.trash_components, None);
+        push(&mut ss.selection_components, None);
+        push(&mut ss.chrono_components, None);
+        push(&mut ss.render_components, None);
+        for (t, marker) in [false, true, false, true].into_iter().enumerate() {
+            let options = SmoothOptionsV4::default();
+            let style = if marker {
+                BrushStrokeStyle::Marker { options }
+            } else {
+                BrushStrokeStyle::Solid { options }
+            };
+            push(
+                &mut ss.strokes,
+                Some(crate::rnotev0_4::StrokeStyle::BrushStroke(BrushStrokeV4 {
+                    elements: vec![ElementV4::default(); 2],
+                    style,
+                    ..BrushStrokeV4::default()
+                })),
+            );
+            push(&mut ss.trash_components, Some(Default::default()));
+            push(&mut ss.selection_components, Some(Default::default()));
+            push(
+                &mut ss.chrono_components,
+                Some(crate::rnotev0_4::ChronoComponent { t: t as u32 + 1 }),
+            );
+            push(&mut ss.render_components, Some(Default::default()));
+        }
+        ss.chrono_counter = 4;
+
+        let store = StoreSnapshot::from(ss);
+
+        assert_eq!(chrono_order(&store), vec![2, 4, 1, 3]);
+        store.assert_valid().unwrap();
+        for (key, stroke, ..) in store.strokes() {
+            let alpha = match stroke.style() {
+                Some(Style::Smooth(options)) => options.stroke_color.unwrap().a,
+                style => panic!("unexpected style {style:?}"),
+            };
+            if key.index % 2 == 0 {
+                assert!(alpha <= BrushStroke::MARKER_ALPHA_MAX);
+            }
         }
     }
 }
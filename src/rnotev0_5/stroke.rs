@@ -1,15 +1,19 @@
+use clap::ArgEnum;
+use p2d::bounding_volume::AABB;
 use serde::{Deserialize, Serialize};
 
-use self::bitmapimage::BitmapImage;
-use self::brushstroke::BrushStroke;
-use self::shapestroke::ShapeStroke;
-use self::vectorimage::VectorImage;
+pub use self::bitmapimage::BitmapImage;
+pub use self::brushstroke::BrushStroke;
+pub use self::shapestroke::ShapeStroke;
+pub use self::style::{Style, StyleKind};
+pub use self::vectorimage::VectorImage;
+use super::{ShapeBehaviour, TransformBehaviour};
 
-mod bitmapimage;
-mod brushstroke;
-mod shapestroke;
-mod style;
-mod vectorimage;
+pub mod bitmapimage;
+pub mod brushstroke;
+pub mod shapestroke;
+pub mod style;
+pub mod vectorimage;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename = "stroke")]
@@ -24,12 +28,117 @@ pub enum Stroke {
     BitmapImage(BitmapImage),
 }
 
+impl From<BitmapImage> for Stroke {
+    fn from(bitmapimage: BitmapImage) -> Self {
+        Self::BitmapImage(bitmapimage)
+    }
+}
+
+impl From<VectorImage> for Stroke {
+    fn from(vectorimage: VectorImage) -> Self {
+        Self::VectorImage(vectorimage)
+    }
+}
+
+/// The type of a stroke
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StrokeType {
+    #[clap(name = "brushstroke")]
+    BrushStroke,
+    #[clap(name = "shapestroke")]
+    ShapeStroke,
+    #[clap(name = "vectorimage")]
+    VectorImage,
+    #[clap(name = "bitmapimage")]
+    BitmapImage,
+}
+
 impl Default for Stroke {
     fn default() -> Self {
         Self::BrushStroke(BrushStroke::default())
     }
 }
 
+impl Stroke {
+    /// The type of the stroke
+    pub fn stroke_type(&self) -> StrokeType {
+        match self {
+            Self::BrushStroke(_) => StrokeType::BrushStroke,
+            Self::ShapeStroke(_) => StrokeType::ShapeStroke,
+            Self::VectorImage(_) => StrokeType::VectorImage,
+            Self::BitmapImage(_) => StrokeType::BitmapImage,
+        }
+    }
+
+    /// The name of the stroke type, as it is serialized
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::BrushStroke(_) => "brushstroke",
+            Self::ShapeStroke(_) => "shapestroke",
+            Self::VectorImage(_) => "vectorimage",
+            Self::BitmapImage(_) => "bitmapimage",
+        }
+    }
+
+    /// The style of the stroke, if it has one
+    pub fn style(&self) -> Option<&Style> {
+        match self {
+            Self::BrushStroke(brushstroke) => Some(&brushstroke.style),
+            Self::ShapeStroke(shapestroke) => Some(&shapestroke.style),
+            Self::VectorImage(_) | Self::BitmapImage(_) => None,
+        }
+    }
+
+    /// The style of the stroke, mutable. Images have no style.
+    pub fn style_mut(&mut self) -> Option<&mut Style> {
+        match self {
+            Self::BrushStroke(brushstroke) => Some(&mut brushstroke.style),
+            Self::ShapeStroke(shapestroke) => Some(&mut shapestroke.style),
+            Self::VectorImage(_) | Self::BitmapImage(_) => None,
+        }
+    }
+}
+
+impl ShapeBehaviour for Stroke {
+    fn bounds(&self) -> AABB {
+        match self {
+            Self::BrushStroke(brushstroke) => brushstroke.bounds(),
+            Self::ShapeStroke(shapestroke) => shapestroke.bounds(),
+            Self::VectorImage(vectorimage) => vectorimage.bounds(),
+            Self::BitmapImage(bitmapimage) => bitmapimage.bounds(),
+        }
+    }
+}
+
+impl TransformBehaviour for Stroke {
+    fn translate(&mut self, offset: na::Vector2<f64>) {
+        match self {
+            Self::BrushStroke(brushstroke) => brushstroke.translate(offset),
+            Self::ShapeStroke(shapestroke) => shapestroke.translate(offset),
+            Self::VectorImage(vectorimage) => vectorimage.translate(offset),
+            Self::BitmapImage(bitmapimage) => bitmapimage.translate(offset),
+        }
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        match self {
+            Self::BrushStroke(brushstroke) => brushstroke.scale(scale),
+            Self::ShapeStroke(shapestroke) => shapestroke.scale(scale),
+            Self::VectorImage(vectorimage) => vectorimage.scale(scale),
+            Self::BitmapImage(bitmapimage) => bitmapimage.scale(scale),
+        }
+    }
+
+    fn rotate(&mut self, angle: f64, center: na::Point2<f64>) {
+        match self {
+            Self::BrushStroke(brushstroke) => brushstroke.rotate(angle, center),
+            Self::ShapeStroke(shapestroke) => shapestroke.rotate(angle, center),
+            Self::VectorImage(vectorimage) => vectorimage.rotate(angle, center),
+            Self::BitmapImage(bitmapimage) => bitmapimage.rotate(angle, center),
+        }
+    }
+}
+
 impl TryFrom<crate::rnotev0_4::StrokeStyle> for Stroke {
     type Error = anyhow::Error;
     fn try_from(s: crate::rnotev0_4::StrokeStyle) -> anyhow::Result<Stroke> {
@@ -42,3 +151,72 @@ impl TryFrom<crate::rnotev0_4::StrokeStyle> for Stroke {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::style::roughoptions::RoughOptions;
+    use super::*;
+    use crate::rnotev0_5::shapes::{Rectangle, Shape};
+    use crate::rnotev0_5::tests::{line_stroke, rectangle_stroke};
+
+    #[test]
+    fn brushstroke_bounds_follow_the_pressure() {
+        let mut stroke = line_stroke(na::vector![0.0, 0.0], na::vector![10.0, 0.0]);
+
+        // the default width of 1.0 at a pressure of 0.5
+        let bounds = stroke.bounds();
+        assert_eq!(bounds.mins, na::point![-0.25, -0.25]);
+        assert_eq!(bounds.maxs, na::point![10.25, 0.25]);
+
+        if let Stroke::BrushStroke(brushstroke) = &mut stroke {
+            if let Style::Smooth(options) = &mut brushstroke.style {
+                options.stroke_width = 4.0;
+                options.segment_constant_width = true;
+            }
+        }
+        let bounds = stroke.bounds();
+        assert_eq!(bounds.mins, na::point![-2.0, -2.0]);
+        assert_eq!(bounds.maxs, na::point![12.0, 2.0]);
+    }
+
+    #[test]
+    fn shapestroke_bounds_include_the_width_and_rough_margin() {
+        let smooth = rectangle_stroke(na::point![0.0, 0.0], na::point![10.0, 10.0]);
+        assert_eq!(smooth.bounds().mins, na::point![-0.5, -0.5]);
+        assert_eq!(smooth.bounds().maxs, na::point![10.5, 10.5]);
+
+        let rough = Stroke::ShapeStroke(ShapeStroke::new(
+            Shape::Rectangle(Rectangle::from_p2d_aabb(AABB::new(
+                na::point![0.0, 0.0],
+                na::point![10.0, 10.0],
+            ))),
+            Style::Rough(Default::default()),
+        ));
+        let loosening = 0.5 + RoughOptions::ROUGH_BOUNDS_MARGIN;
+        assert_eq!(rough.bounds().mins, na::point![-loosening, -loosening]);
+        assert_eq!(
+            rough.bounds().maxs,
+            na::point![10.0 + loosening, 10.0 + loosening]
+        );
+    }
+
+    #[test]
+    fn bitmapimage_bounds_are_the_rectangle() {
+        let mut bytes = vec![];
+        image::DynamicImage::ImageRgba8(image::RgbaImage::new(4, 2))
+            .write_to(&mut bytes, image::ImageOutputFormat::Png)
+            .unwrap();
+        let stroke = Stroke::BitmapImage(
+            BitmapImage::import_from_image_bytes(
+                &bytes,
+                image::ImageFormat::Png,
+                na::vector![10.0, 20.0],
+                Some(8.0),
+            )
+            .unwrap(),
+        );
+
+        assert_eq!(stroke.bounds().mins, na::point![10.0, 20.0]);
+        assert_eq!(stroke.bounds().maxs, na::point![18.0, 24.0]);
+    }
+}
@@ -9,6 +9,7 @@ use serde::{Deserialize, Serialize};
 use crate::rnotev0_4::strokes::bitmapimage::BitmapImageFormat;
 use crate::rnotev0_5::base64;
 use crate::rnotev0_5::shapes::Rectangle;
+use crate::rnotev0_5::{Color, ShapeBehaviour, TransformBehaviour};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename = "bitmapimage")]
@@ -32,9 +33,48 @@ impl Default for BitmapImage {
 impl BitmapImage {
     /// The default offset in surface coords when importing a bitmap image
     pub const IMPORT_OFFSET_DEFAULT: na::Vector2<f64> = na::vector![32.0, 32.0];
+
+    /// Imports an encoded image, placing its upper left corner at the position.
+    /// The size in the coordinate space of the doc is the pixel size, unless a width is given. The aspect ratio is kept.
+    pub fn import_from_image_bytes(
+        bytes: &[u8],
+        format: image::ImageFormat,
+        pos: na::Vector2<f64>,
+        width: Option<f64>,
+    ) -> anyhow::Result<Self> {
+        let mut image = Image::try_from_encoded_bytes(bytes, format)?;
+        image.assert_valid()?;
+
+        let pixel_size = na::vector![f64::from(image.pixel_width), f64::from(image.pixel_height)];
+        let size = width.map_or(pixel_size, |width| pixel_size * (width / pixel_size[0]));
+        let rectangle = Rectangle::from_p2d_aabb(AABB::new(pos.into(), (pos + size).into()));
+        image.rect = rectangle;
+
+        Ok(Self { image, rectangle })
+    }
 }
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+impl TransformBehaviour for BitmapImage {
+    fn translate(&mut self, offset: na::Vector2<f64>) {
+        self.rectangle.translate(offset);
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        self.rectangle.scale(scale);
+    }
+
+    fn rotate(&mut self, angle: f64, center: na::Point2<f64>) {
+        self.rectangle.rotate(angle, center);
+    }
+}
+
+impl ShapeBehaviour for BitmapImage {
+    fn bounds(&self) -> AABB {
+        self.rectangle.bounds()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ImageMemoryFormat {
     R8g8b8a8Premultiplied,
     B8g8r8a8Premultiplied,
@@ -84,7 +124,9 @@ impl From<image::DynamicImage> for Image {
         let pixel_width = dynamic_image.width();
         let pixel_height = dynamic_image.height();
         let memory_format = ImageMemoryFormat::R8g8b8a8Premultiplied;
-        let data = dynamic_image.into_rgba8().to_vec();
+        // into_rgba8() yields straight alpha
+        let mut data = dynamic_image.into_rgba8().into_vec();
+        premultiply(&mut data);
 
         let bounds = AABB::new(
             na::point![0.0, 0.0],
@@ -104,7 +146,7 @@ impl From<image::DynamicImage> for Image {
 impl Image {
     pub fn assert_valid(&self) -> anyhow::Result<()> {
         if self.pixel_width == 0
-            || self.pixel_width == 0
+            || self.pixel_height == 0
             || self.data.len() as u32 != 4 * self.pixel_width * self.pixel_height
         {
             Err(anyhow::anyhow!(
@@ -134,57 +176,28 @@ impl Image {
                 return Ok(());
             }
             ImageMemoryFormat::B8g8r8a8Premultiplied => {
-                let imgbuf_bgra8 = image::ImageBuffer::<image::Bgra<u8>, Vec<u8>>::from_vec(
-                    self.pixel_width,
-                    self.pixel_height,
-                    self.data.clone(),
-                )
-                .ok_or(anyhow::anyhow!(
-                    "RgbaImage::from_vec() failed in Image to_imgbuf() for image with Format {:?}",
-                    self.memory_format
-                ))?;
-
-                let dynamic_image = image::DynamicImage::ImageBgra8(imgbuf_bgra8).into_rgba8();
-
-                *self = Self {
-                    pixel_width: self.pixel_width,
-                    pixel_height: self.pixel_height,
-                    data: dynamic_image.into_vec(),
-                    rect: self.rect.clone(),
-                    memory_format: ImageMemoryFormat::R8g8b8a8Premultiplied,
-                };
+                // Both formats are premultiplied, so only the color channels need to be swapped
+                swap_red_blue(&mut self.data);
+                self.memory_format = ImageMemoryFormat::R8g8b8a8Premultiplied;
             }
         }
 
         Ok(())
     }
 
-    pub fn to_imgbuf(self) -> Result<image::ImageBuffer<image::Rgba<u8>, Vec<u8>>, anyhow::Error> {
-        self.assert_valid()?;
+    /// Converts the image into an image buffer with straight (not premultiplied) alpha, as expected by the image crate.
+    pub fn to_imgbuf(
+        mut self,
+    ) -> Result<image::ImageBuffer<image::Rgba<u8>, Vec<u8>>, anyhow::Error> {
+        self.convert_to_rgba8pre()?;
+        unpremultiply(&mut self.data);
 
-        match self.memory_format {
-            ImageMemoryFormat::R8g8b8a8Premultiplied => {
-                image::RgbaImage::from_vec(self.pixel_width, self.pixel_height, self.data).ok_or(
-                    anyhow::anyhow!(
-                    "RgbaImage::from_vec() failed in Image to_imgbuf() for image with Format {:?}",
-                    self.memory_format
-                ),
-                )
-            }
-            ImageMemoryFormat::B8g8r8a8Premultiplied => {
-                let imgbuf_bgra8 = image::ImageBuffer::<image::Bgra<u8>, Vec<u8>>::from_vec(
-                    self.pixel_width,
-                    self.pixel_height,
-                    self.data,
-                )
-                .ok_or(anyhow::anyhow!(
-                    "RgbaImage::from_vec() failed in Image to_imgbuf() for image with Format {:?}",
-                    self.memory_format
-                ))?;
-
-                Ok(image::DynamicImage::ImageBgra8(imgbuf_bgra8).into_rgba8())
-            }
-        }
+        image::RgbaImage::from_vec(self.pixel_width, self.pixel_height, self.data).ok_or(
+            anyhow::anyhow!(
+                "RgbaImage::from_vec() failed in Image to_imgbuf() for image with Format {:?}",
+                self.memory_format
+            ),
+        )
     }
 
     pub fn into_encoded_bytes(
@@ -204,6 +217,85 @@ impl Image {
 
         Ok(bytes_buf)
     }
+
+    /// Maps the color of every pixel, in straight alpha. The memory format is converted to R8g8b8a8Premultiplied.
+    pub fn map_colors(&mut self, mut f: impl FnMut(Color) -> Color) -> anyhow::Result<()> {
+        self.convert_to_rgba8pre()?;
+        unpremultiply(&mut self.data);
+
+        for pixel in self.data.chunks_exact_mut(4) {
+            let color = f(Color {
+                r: f64::from(pixel[0]) / 255.0,
+                g: f64::from(pixel[1]) / 255.0,
+                b: f64::from(pixel[2]) / 255.0,
+                a: f64::from(pixel[3]) / 255.0,
+            });
+            for (channel, value) in pixel.iter_mut().zip([color.r, color.g, color.b, color.a]) {
+                *channel = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+            }
+        }
+        premultiply(&mut self.data);
+
+        Ok(())
+    }
+
+    /// Resizes the image data to the given pixel size. The memory format is converted to R8g8b8a8Premultiplied.
+    pub fn resize(&mut self, pixel_width: u32, pixel_height: u32) -> anyhow::Result<()> {
+        self.convert_to_rgba8pre()?;
+        // Filtering premultiplied data avoids color fringes from fully transparent pixels
+        let imgbuf = image::RgbaImage::from_vec(
+            self.pixel_width,
+            self.pixel_height,
+            std::mem::take(&mut self.data),
+        )
+        .ok_or(anyhow::anyhow!(
+            "RgbaImage::from_vec() failed in Image resize() for image with Format {:?}",
+            self.memory_format
+        ))?;
+        let resized = image::imageops::resize(
+            &imgbuf,
+            pixel_width,
+            pixel_height,
+            image::imageops::FilterType::Triangle,
+        );
+
+        self.data = resized.into_vec();
+        self.pixel_width = pixel_width;
+        self.pixel_height = pixel_height;
+        self.memory_format = ImageMemoryFormat::R8g8b8a8Premultiplied;
+
+        Ok(())
+    }
+}
+
+/// Premultiplies the color channels of 8bit RGBA or BGRA data with its alpha channel
+fn premultiply(data: &mut [u8]) {
+    for pixel in data.chunks_exact_mut(4) {
+        let alpha = u32::from(pixel[3]);
+        for channel in pixel[..3].iter_mut() {
+            *channel = ((u32::from(*channel) * alpha + 127) / 255) as u8;
+        }
+    }
+}
+
+/// Reverts the premultiplication of the color channels of 8bit RGBA or BGRA data
+fn unpremultiply(data: &mut [u8]) {
+    for pixel in data.chunks_exact_mut(4) {
+        let alpha = u32::from(pixel[3]);
+        for channel in pixel[..3].iter_mut() {
+            // Fully transparent pixels have no recoverable color
+            *channel = (u32::from(*channel) * 255 + alpha / 2)
+                .checked_div(alpha)
+                .map_or(0, |value| value.min(255) as u8);
+        }
+    }
+}
+
+/// Swaps the red and blue channels, converting between RGBA and BGRA
+fn swap_red_blue(data: &mut [u8]) {
+    for pixel in data.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+    }
 }
 
 impl TryFrom<crate::rnotev0_4::strokes::bitmapimage::BitmapImage> for BitmapImage {
@@ -221,10 +313,128 @@ impl TryFrom<crate::rnotev0_4::strokes::bitmapimage::BitmapImage> for BitmapImag
                 BitmapImageFormat::Png => image::ImageFormat::Png,
             },
         )?;
-        
+
         Ok(BitmapImage {
             image,
             rectangle: bi.rectangle.into(),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Straight alpha RGBA pixels: opaque, semi-transparent, barely visible and fully transparent
+    const PIXELS: [[u8; 4]; 4] = [
+        [200, 100, 50, 255],
+        [255, 0, 0, 128],
+        [0, 255, 64, 10],
+        [90, 90, 90, 0],
+    ];
+
+    fn png_fixture() -> Vec<u8> {
+        let imgbuf = image::RgbaImage::from_vec(2, 2, PIXELS.concat()).unwrap();
+        let mut bytes = vec![];
+        image::DynamicImage::ImageRgba8(imgbuf)
+            .write_to(&mut bytes, image::ImageOutputFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    fn assert_pixels_near(data: &[u8], expected: &[[u8; 4]], tolerance: u8) {
+        for (pixel, expected) in data.chunks_exact(4).zip(expected.iter()) {
+            for (value, expected_value) in pixel.iter().zip(expected.iter()) {
+                assert!(
+                    value.abs_diff(*expected_value) <= tolerance,
+                    "pixel {pixel:?} differs from expected {expected:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn decoding_premultiplies() {
+        let image = Image::try_from_encoded_bytes(&png_fixture(), image::ImageFormat::Png).unwrap();
+
+        image.assert_valid().unwrap();
+        assert_pixels_near(
+            &image.data,
+            &[
+                [200, 100, 50, 255],
+                [128, 0, 0, 128],
+                [0, 10, 3, 10],
+                [0, 0, 0, 0],
+            ],
+            0,
+        );
+    }
+
+    #[test]
+    fn encoding_roundtrip_restores_straight_alpha() {
+        let image = Image::try_from_encoded_bytes(&png_fixture(), image::ImageFormat::Png).unwrap();
+        let bytes = image
+            .into_encoded_bytes(image::ImageOutputFormat::Png)
+            .unwrap();
+        let decoded = image::load_from_memory(&bytes).unwrap().into_rgba8();
+
+        assert_pixels_near(&decoded.as_raw()[..8], &PIXELS[..2], 1);
+        // The precision of the color channels of nearly transparent pixels is lost by premultiplying
+        assert_eq!(decoded.as_raw()[11], 10);
+        assert_eq!(&decoded.as_raw()[12..], &[0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn bgra_conversion_keeps_premultiplied_values() {
+        let mut image = Image {
+            data: vec![0, 0, 128, 128, 50, 100, 200, 255],
+            pixel_width: 2,
+            pixel_height: 1,
+            memory_format: ImageMemoryFormat::B8g8r8a8Premultiplied,
+            ..Image::default()
+        };
+
+        image.convert_to_rgba8pre().unwrap();
+        assert_eq!(image.data, vec![128, 0, 0, 128, 200, 100, 50, 255]);
+
+        let imgbuf = image.to_imgbuf().unwrap();
+        assert_pixels_near(imgbuf.as_raw(), &[[255, 0, 0, 128], [200, 100, 50, 255]], 0);
+    }
+
+    #[test]
+    fn resize_has_no_transparent_fringe() {
+        // A red pixel next to a fully transparent one, which has garbage green in straight alpha
+        let imgbuf = image::RgbaImage::from_vec(2, 1, vec![255, 0, 0, 255, 0, 255, 0, 0]).unwrap();
+        let mut image = Image::from(image::DynamicImage::ImageRgba8(imgbuf));
+
+        image.resize(1, 1).unwrap();
+        let pixel = image.to_imgbuf().unwrap().into_raw();
+
+        assert_eq!(pixel[1], 0);
+        assert!(pixel[0] >= 254);
+    }
+
+    #[test]
+    fn map_colors_works_on_straight_alpha() {
+        let mut image =
+            Image::try_from_encoded_bytes(&png_fixture(), image::ImageFormat::Png).unwrap();
+
+        image.map_colors(|color| color.to_grayscale()).unwrap();
+        let imgbuf = image.to_imgbuf().unwrap();
+
+        // the semi-transparent red pixel has the luminance of red, not of the premultiplied red
+        assert_pixels_near(&imgbuf.as_raw()[4..8], &[[54, 54, 54, 128]], 1);
+        assert_eq!(imgbuf.as_raw()[0], imgbuf.as_raw()[2]);
+    }
+
+    #[test]
+    fn invalid_height_is_rejected() {
+        let image = Image {
+            pixel_width: 1,
+            pixel_height: 0,
+            ..Image::default()
+        };
+
+        assert!(image.assert_valid().is_err());
+    }
+}
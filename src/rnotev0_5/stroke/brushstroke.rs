@@ -1,9 +1,13 @@
 use std::collections::VecDeque;
 
+use p2d::bounding_volume::{BoundingVolume, AABB};
 use serde::{Deserialize, Serialize};
 
+use super::style::roughoptions::RoughOptions;
+use super::style::smoothoptions::SmoothOptions;
 use super::style::Style;
 use crate::rnotev0_5::penpath::{Element, PenPath, Segment};
+use crate::rnotev0_5::{Color, ShapeBehaviour, TransformBehaviour};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename = "brushstroke")]
@@ -28,6 +32,8 @@ impl Default for BrushStroke {
 impl BrushStroke {
     /// when one of the extents of the stroke is above this threshold, images are generated seperately for each stroke segment (to avoid very large images)
     pub const IMAGES_SEGMENTS_THRESHOLD: f64 = 1000.0;
+    /// The maximum alpha of the stroke color of converted v0.4 markers, to keep them semi-transparent
+    pub const MARKER_ALPHA_MAX: f64 = 0.5;
 
     pub fn new(segment: Segment, style: Style) -> Self {
         let path = PenPath::new_w_segment(segment);
@@ -46,13 +52,67 @@ impl BrushStroke {
     pub fn push_segment(&mut self, segment: Segment) {
         self.path.push_back(segment);
     }
+
+    /// The maximum half width of the segment when drawn with the style
+    fn segment_half_width(&self, segment: &Segment) -> f64 {
+        let pressure = segment.start().pressure.max(segment.end().pressure);
+
+        match &self.style {
+            Style::Smooth(options) if options.segment_constant_width => options.stroke_width * 0.5,
+            Style::Smooth(options) => options.stroke_width * pressure * 0.5,
+            Style::Textured(options) => {
+                let width = if options.segment_constant_width {
+                    options.stroke_width
+                } else {
+                    options.stroke_width * pressure
+                };
+                // the dots are placed around the segment and can extend beyond its width
+                width * 0.5 + options.radii.max()
+            }
+            Style::Rough(options) => options.stroke_width * 0.5 + RoughOptions::ROUGH_BOUNDS_MARGIN,
+        }
+    }
+}
+
+impl TransformBehaviour for BrushStroke {
+    fn translate(&mut self, offset: na::Vector2<f64>) {
+        self.path.translate(offset);
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        self.path.scale(scale);
+    }
+
+    fn rotate(&mut self, angle: f64, center: na::Point2<f64>) {
+        self.path.rotate(angle, center);
+    }
+}
+
+impl ShapeBehaviour for BrushStroke {
+    /// The bounds of the path segments, loosened by the width of the segments at their pen pressure.
+    fn bounds(&self) -> AABB {
+        self.path
+            .iter()
+            .map(|segment| segment.bounds().loosened(self.segment_half_width(segment)))
+            .reduce(|acc, bounds| acc.merged(&bounds))
+            .unwrap_or_else(|| self.path.bounds())
+    }
 }
 
 impl From<crate::rnotev0_4::strokes::brushstroke::BrushStroke> for BrushStroke {
     fn from(bs: crate::rnotev0_4::strokes::brushstroke::BrushStroke) -> Self {
         let style = match bs.style {
+            // v0.5 has no dedicated marker style, so the look is approximated with a semi-transparent smooth style
+            // with constant width. The store orders markers behind the other strokes when converting.
             crate::rnotev0_4::strokes::brushstroke::BrushStrokeStyle::Marker { options } => {
-                Style::Smooth(options.into())
+                let mut options: SmoothOptions = options.into();
+                options.stroke_color = options.stroke_color.map(|color| Color {
+                    a: color.a.min(Self::MARKER_ALPHA_MAX),
+                    ..color
+                });
+                options.segment_constant_width = true;
+
+                Style::Smooth(options)
             }
             crate::rnotev0_4::strokes::brushstroke::BrushStrokeStyle::Solid { options } => {
                 Style::Smooth(options.into())
@@ -1,7 +1,10 @@
+use p2d::bounding_volume::{BoundingVolume, AABB};
 use serde::{Deserialize, Serialize};
 
+use super::style::roughoptions::RoughOptions;
 use super::style::Style;
 use crate::rnotev0_5::shapes::Shape;
+use crate::rnotev0_5::{ShapeBehaviour, TransformBehaviour};
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename = "shapestroke")]
@@ -18,11 +21,43 @@ impl ShapeStroke {
     }
 }
 
+impl TransformBehaviour for ShapeStroke {
+    fn translate(&mut self, offset: na::Vector2<f64>) {
+        self.shape.translate(offset);
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        self.shape.scale(scale);
+    }
+
+    fn rotate(&mut self, angle: f64, center: na::Point2<f64>) {
+        self.shape.rotate(angle, center);
+    }
+}
+
+impl ShapeBehaviour for ShapeStroke {
+    /// The bounds of the shape, loosened by the stroke width and the margin of rough shapes.
+    fn bounds(&self) -> AABB {
+        let margin = match &self.style {
+            Style::Rough(_) => RoughOptions::ROUGH_BOUNDS_MARGIN,
+            _ => 0.0,
+        };
+
+        self.shape
+            .bounds()
+            .loosened(self.style.stroke_width() * 0.5 + margin)
+    }
+}
+
 impl From<crate::rnotev0_4::strokes::shapestroke::ShapeStroke> for ShapeStroke {
     fn from(ss: crate::rnotev0_4::strokes::shapestroke::ShapeStroke) -> Self {
         let style = match ss.drawstyle {
             crate::rnotev0_4::strokes::shapestroke::ShapeDrawStyle::Rough { options } => {
-                Style::Rough(options.into())
+                let mut options: RoughOptions = options.into();
+                // v0.4 generated the rough shape with the seed of the stroke
+                options.seed = ss.seed.or(options.seed);
+
+                Style::Rough(options)
             }
             crate::rnotev0_4::strokes::shapestroke::ShapeDrawStyle::Smooth { options } => {
                 Style::Smooth(options.into())
@@ -35,3 +70,24 @@ impl From<crate::rnotev0_4::strokes::shapestroke::ShapeStroke> for ShapeStroke {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rnotev0_4::strokes::shapestroke::{ShapeDrawStyle, ShapeStroke as ShapeStrokeV4};
+
+    #[test]
+    fn v0_4_stroke_seed_is_carried_into_the_rough_style() {
+        let v4 = ShapeStrokeV4 {
+            seed: Some(7),
+            drawstyle: ShapeDrawStyle::Rough {
+                options: Default::default(),
+            },
+            ..ShapeStrokeV4::default()
+        };
+
+        let shapestroke = ShapeStroke::from(v4);
+
+        assert_eq!(shapestroke.style.seed(), Some(7));
+    }
+}
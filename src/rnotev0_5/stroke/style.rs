@@ -1,5 +1,8 @@
+use clap::ArgEnum;
 use serde::{Serialize, Deserialize};
 
+use crate::rnotev0_5::Color;
+
 use self::{smoothoptions::SmoothOptions, roughoptions::RoughOptions, texturedoptions::TexturedOptions};
 
 pub mod roughoptions;
@@ -7,7 +10,7 @@ pub mod smoothoptions;
 pub mod textureddotsdistribution;
 pub mod texturedoptions;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 /// A style choice holding the style options inside its variants
 #[serde(rename = "style")]
 pub enum Style {
@@ -22,6 +25,14 @@ pub enum Style {
     Textured(TexturedOptions),
 }
 
+/// The kind of a style, without its options
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum StyleKind {
+    Smooth,
+    Rough,
+    Textured,
+}
+
 impl Default for Style {
     fn default() -> Self {
         Self::Smooth(SmoothOptions::default())
@@ -37,4 +48,120 @@ impl Style {
             Style::Textured(options) => options.stroke_width,
         }
     }
+
+    /// returns the stroke and fill colors which are set, mutable
+    pub fn colors_mut(&mut self) -> Vec<&mut Color> {
+        match self {
+            Style::Smooth(options) => options
+                .stroke_color
+                .iter_mut()
+                .chain(options.fill_color.iter_mut())
+                .collect(),
+            Style::Rough(options) => options
+                .stroke_color
+                .iter_mut()
+                .chain(options.fill_color.iter_mut())
+                .collect(),
+            Style::Textured(options) => options.stroke_color.iter_mut().collect(),
+        }
+    }
+
+    /// returns the kind of the style
+    pub fn kind(&self) -> StyleKind {
+        match self {
+            Style::Smooth(_) => StyleKind::Smooth,
+            Style::Rough(_) => StyleKind::Rough,
+            Style::Textured(_) => StyleKind::Textured,
+        }
+    }
+
+    /// converts the style to the kind, carrying over the width, the colors, the seed and the constant width setting
+    /// where both styles have them. The other options are set to their defaults.
+    pub fn converted_to(&self, kind: StyleKind) -> Style {
+        let (stroke_width, stroke_color, fill_color, seed, segment_constant_width) = match self {
+            Style::Smooth(options) => (
+                options.stroke_width,
+                options.stroke_color,
+                options.fill_color,
+                None,
+                options.segment_constant_width,
+            ),
+            Style::Rough(options) => (
+                options.stroke_width,
+                options.stroke_color,
+                options.fill_color,
+                options.seed,
+                false,
+            ),
+            Style::Textured(options) => (
+                options.stroke_width,
+                options.stroke_color,
+                None,
+                options.seed,
+                options.segment_constant_width,
+            ),
+        };
+
+        match kind {
+            _ if kind == self.kind() => self.clone(),
+            StyleKind::Smooth => Style::Smooth(SmoothOptions {
+                stroke_width,
+                stroke_color,
+                fill_color,
+                segment_constant_width,
+            }),
+            StyleKind::Rough => Style::Rough(RoughOptions {
+                stroke_width,
+                stroke_color,
+                fill_color,
+                seed,
+                ..RoughOptions::default()
+            }),
+            StyleKind::Textured => Style::Textured(TexturedOptions {
+                seed,
+                stroke_width,
+                stroke_color,
+                density: TexturedOptions::DENSITY_DEFAULT,
+                radii: TexturedOptions::RADII_DEFAULT,
+                segment_constant_width,
+                ..TexturedOptions::default()
+            }),
+        }
+    }
+
+    /// returns the seed. Smooth styles have no seed
+    pub fn seed(&self) -> Option<u64> {
+        match self {
+            Style::Smooth(_) => None,
+            Style::Rough(options) => options.seed,
+            Style::Textured(options) => options.seed,
+        }
+    }
+
+    /// sets the seed. Smooth styles have no seed, so it is ignored for them
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        match self {
+            Style::Smooth(_) => {}
+            Style::Rough(options) => options.seed = seed,
+            Style::Textured(options) => options.seed = seed,
+        }
+    }
+
+    /// removes the fill color, returning it if it was set. Textured styles have no fill color
+    pub fn take_fill_color(&mut self) -> Option<Color> {
+        match self {
+            Style::Smooth(options) => options.fill_color.take(),
+            Style::Rough(options) => options.fill_color.take(),
+            Style::Textured(_) => None,
+        }
+    }
+
+    /// sets the stroke width. available on all styles
+    pub fn set_stroke_width(&mut self, stroke_width: f64) {
+        match self {
+            Style::Smooth(options) => options.stroke_width = stroke_width,
+            Style::Rough(options) => options.stroke_width = stroke_width,
+            Style::Textured(options) => options.stroke_width = stroke_width,
+        }
+    }
 }
\ No newline at end of file
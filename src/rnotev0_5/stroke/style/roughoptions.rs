@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 use crate::rnotev0_5::Color;
 
 /// The rough options
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(default, rename = "rough_options")]
 pub struct RoughOptions {
     /// limits the maximum offset the randomness is allowed to create.
@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::rnotev0_5::Color;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(default, rename = "smooth_options")]
 /// Options for shapes that can be drawn smoothly (plain)
 pub struct SmoothOptions {
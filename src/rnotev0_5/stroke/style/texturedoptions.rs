@@ -5,7 +5,7 @@ use crate::rnotev0_5::Color;
 
 /// The Options of how a textured shape should look
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(default, rename = "textured_options")]
 pub struct TexturedOptions {
     /// An optional seed to generate reproducable strokes
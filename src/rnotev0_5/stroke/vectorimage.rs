@@ -1,6 +1,8 @@
+use p2d::bounding_volume::AABB;
 use serde::{Deserialize, Serialize};
 
 use crate::rnotev0_5::shapes::Rectangle;
+use crate::rnotev0_5::{ShapeBehaviour, TransformBehaviour};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename = "vectorimage")]
@@ -26,6 +28,85 @@ impl Default for VectorImage {
 impl VectorImage {
     /// The default offset in surface coords when importing a vector image
     pub const IMPORT_OFFSET_DEFAULT: na::Vector2<f64> = na::vector![32.0, 32.0];
+
+    /// Imports a svg, placing its upper left corner at the position.
+    /// The size in the coordinate space of the doc is the intrinsic size of the svg, unless a width is given. The aspect ratio is kept.
+    pub fn import_from_svg_data(
+        svg_data: &str,
+        pos: na::Vector2<f64>,
+        width: Option<f64>,
+    ) -> anyhow::Result<Self> {
+        let intrinsic_size = svg_intrinsic_size(svg_data).ok_or_else(|| {
+            anyhow::anyhow!("failed to determine the intrinsic size of the svg from its width, height or viewBox")
+        })?;
+        let size = width.map_or(intrinsic_size, |width| {
+            intrinsic_size * (width / intrinsic_size[0])
+        });
+
+        Ok(Self {
+            svg_data: svg_data.to_string(),
+            intrinsic_size,
+            rectangle: Rectangle::from_p2d_aabb(AABB::new(pos.into(), (pos + size).into())),
+        })
+    }
+}
+
+/// Reads the intrinsic size from the width and height attributes of the root svg element, or its viewBox
+fn svg_intrinsic_size(svg_data: &str) -> Option<na::Vector2<f64>> {
+    let start = svg_data.find("<svg")?;
+    let root = &svg_data[start..start + svg_data[start..].find('>')?];
+
+    let attr = |name: &str| {
+        let pattern = format!("{name}=");
+        // the attribute name must not be the suffix of another attribute name
+        let (attr_start, _) = root
+            .match_indices(&pattern)
+            .find(|(i, _)| root[..*i].chars().last().is_some_and(char::is_whitespace))?;
+        let value = &root[attr_start + pattern.len()..];
+        let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        value[1..].split(quote).next().map(|value| value.trim())
+    };
+    let length = |name: &str| {
+        attr(name)?
+            .trim_end_matches("px")
+            .parse::<f64>()
+            .ok()
+            .filter(|length| *length > 0.0)
+    };
+
+    if let (Some(width), Some(height)) = (length("width"), length("height")) {
+        return Some(na::vector![width, height]);
+    }
+    let view_box = attr("viewBox")?
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|value| !value.is_empty())
+        .map(|value| value.parse::<f64>().ok())
+        .collect::<Option<Vec<f64>>>()?;
+
+    match view_box[..] {
+        [_, _, width, height] if width > 0.0 && height > 0.0 => Some(na::vector![width, height]),
+        _ => None,
+    }
+}
+
+impl TransformBehaviour for VectorImage {
+    fn translate(&mut self, offset: na::Vector2<f64>) {
+        self.rectangle.translate(offset);
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        self.rectangle.scale(scale);
+    }
+
+    fn rotate(&mut self, angle: f64, center: na::Point2<f64>) {
+        self.rectangle.rotate(angle, center);
+    }
+}
+
+impl ShapeBehaviour for VectorImage {
+    fn bounds(&self) -> AABB {
+        self.rectangle.bounds()
+    }
 }
 
 impl From<crate::rnotev0_4::strokes::vectorimage::VectorImage> for VectorImage {
@@ -2,7 +2,7 @@
 use serde::{Deserialize, Serialize};
 
 /// To be used as state in a stroke to help implement the StrokeBehaviour trait
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[serde(default, rename = "transform")]
 pub struct Transform {
     #[serde(rename = "affine")]
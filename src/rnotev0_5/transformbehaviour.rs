@@ -0,0 +1,9 @@
+/// Trait for types that can be (geometrically) transformed
+pub trait TransformBehaviour {
+    /// translates (as in moves) the type by offset
+    fn translate(&mut self, offset: na::Vector2<f64>);
+    /// scales the type by the scale factors, relative to the origin of the coordinate space
+    fn scale(&mut self, scale: na::Vector2<f64>);
+    /// rotates the type by the angle (in radians) around the center
+    fn rotate(&mut self, angle: f64, center: na::Point2<f64>);
+}
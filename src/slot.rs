@@ -1,3 +1,4 @@
+use anyhow::anyhow;
 use serde::{Serialize, Deserialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -6,4 +7,37 @@ pub struct SerdeSlot<T> {
     pub version: u32
 }
 
-pub type SlotMap<T> = Vec<SerdeSlot<T>>;
\ No newline at end of file
+pub type SlotMap<T> = Vec<SerdeSlot<T>>;
+
+impl<T> SerdeSlot<T> {
+    /// A vacant slot which was never occupied
+    pub fn vacant() -> Self {
+        Self {
+            value: None,
+            version: 0,
+        }
+    }
+}
+
+/// Asserts the invariants which slot maps require when they are deserialized by Rnote:
+/// the first slot is reserved and must be vacant, and exactly the occupied slots have an odd version.
+pub fn assert_valid<T>(slots: &SlotMap<T>) -> anyhow::Result<()> {
+    match slots.first() {
+        None => return Err(anyhow!("the reserved first slot is missing")),
+        Some(slot) if slot.version % 2 == 1 || slot.value.is_some() => {
+            return Err(anyhow!("the reserved first slot is not vacant"))
+        }
+        Some(_) => {}
+    }
+    if let Some(index) = slots
+        .iter()
+        .position(|slot| (slot.version % 2 == 1) != slot.value.is_some())
+    {
+        return Err(anyhow!(
+            "the occupation of slot {index} is inconsistent with its version {}",
+            slots[index].version
+        ));
+    }
+
+    Ok(())
+}